@@ -2,6 +2,7 @@ extern crate clap;
 use clap::{App, Arg, ArgGroup};
 use recut::*;
 use regex::RegexSet;
+use std::{io::stdout, process};
 
 
 fn main() {
@@ -15,14 +16,34 @@ fn main() {
                 .help("Specifies a range of bytes to be returned. e.g b20:-2,25, will print from the first 20 bytes to until the second to last byte, followed by the 25th byte")
                 .takes_value(true)
                 .validator(check_formatted_lists)
+                .allow_hyphen_values(true)
         )
         .arg(
             Arg::with_name("Characters")
                 .short("c")
-                .help("Specifies a range of characters which will be returned. e.g c20:-2,25, will print from the first 20 characters to until the second to last character, followed by the 25th character")
+                .help("Specifies a range of characters which will be returned. e.g c20:-2,25, will print from the first 20 characters to until the second to last character, followed by the 25th character. Characters are unicode scalar values so multi-byte characters count once")
                 .takes_value(true)
                 .value_name("LIST")
                 .validator(check_formatted_lists)
+                .allow_hyphen_values(true)
+        )
+        .arg(
+            Arg::with_name("Graphemes")
+                .short("g")
+                .help("Like -c but counts grapheme clusters, so an accented letter, flag or skin toned emoji is one character as it appears on screen")
+                .takes_value(true)
+                .value_name("LIST")
+                .validator(check_formatted_lists)
+                .allow_hyphen_values(true)
+        )
+        .arg(
+            Arg::with_name("Columns")
+                .short("w")
+                .help("Like -c but counts terminal display columns, East Asian wide characters cover two columns. Use --wide for wide characters split by the edge of a range")
+                .takes_value(true)
+                .value_name("LIST")
+                .validator(check_formatted_lists)
+                .allow_hyphen_values(true)
         ).arg(
             Arg::with_name("Fields")
             .short("f")
@@ -30,6 +51,7 @@ fn main() {
             .value_name("LIST")
             .takes_value(true)
             .validator(check_formatted_lists)
+            .allow_hyphen_values(true)
         )
         .arg(
             Arg::with_name("MatchField")
@@ -38,7 +60,7 @@ fn main() {
             .takes_value(true)
         )
         .group(ArgGroup::with_name("Range")
-            .args(&["Bytes","Characters","Fields","MatchField"])
+            .args(&["Bytes","Characters","Graphemes","Columns","Fields","MatchField"])
             .required(true)
         )
         .group(ArgGroup::with_name("DataSeperator")
@@ -56,10 +78,127 @@ fn main() {
             Arg::with_name("Split")
             .short("s")
             .takes_value(true)
-            .requires("n")
+            .requires("DataSeperator")
             .help("Like D but for string literals only,fields option (-f) or MatchField option (-m) must be used.")
             .conflicts_with("Delimiter")
         ) 
+        .arg(
+            Arg::with_name("Aligned")
+            .long("aligned")
+            .requires("DataSeperator")
+            .conflicts_with_all(&["Delimiter", "Split"])
+            .help("Instead of a delimiter infer fixed width columns from whitespace lined up across the sampled lines, for aligned tables where fields can contain single spaces")
+        )
+        .arg(
+            Arg::with_name("Widths")
+            .long("widths")
+            .value_name("WIDTHS")
+            .takes_value(true)
+            .requires("DataSeperator")
+            .conflicts_with_all(&["Delimiter", "Split", "Aligned"])
+            .validator(check_widths)
+            .help("Split into fixed width fields instead of by a delimiter, e.g 8,12,5,* is fields of 8, 12 and 5 characters followed by the rest of the line")
+        )
+        .arg(
+            Arg::with_name("WidthsIn")
+            .long("widths-in")
+            .value_name("UNIT")
+            .takes_value(true)
+            .possible_values(&["chars", "columns"])
+            .requires("Widths")
+            .help("What --widths are counted in: chars (default) or terminal display columns, where East Asian wide characters cover two")
+        )
+        .arg(
+            Arg::with_name("KeepQuotes")
+            .long("keep-quotes")
+            .requires("DataSeperator")
+            .help("Leaves quoted fields as they are in the input with their quotes and escapes. By default the quotes are stripped and doubled quotes collapsed, and header names are unquoted before -m matches them. Only for plain output as the other formats quote fields themselves")
+        )
+        .arg(
+            Arg::with_name("Quote")
+            .long("quote")
+            .value_name("CHAR")
+            .takes_value(true)
+            .requires("DataSeperator")
+            .validator(check_char)
+            .help("The character fields are quoted with, if not present \" or ' is inferred from the first lines. A quote only opens a field at the start of one, so 5\" or O'Brien are plain data, and a quoted field can span several lines")
+        )
+        .arg(
+            Arg::with_name("Escape")
+            .long("escape")
+            .value_name("CHAR")
+            .takes_value(true)
+            .requires("DataSeperator")
+            .validator(check_char)
+            .help("Escapes the character after it inside a quoted field e.g \\, if not present quotes are escaped by doubling them as in CSV")
+        )
+        .arg(
+            Arg::with_name("Trim")
+            .long("trim")
+            .value_name("POLICY")
+            .takes_value(true)
+            .possible_values(&["none", "both", "left", "right"])
+            .help("Which whitespace is trimmed from the ends of fields that are not quoted: none, both (default), left or right")
+        )
+        .arg(
+            Arg::with_name("TrimQuoted")
+            .long("trim-quoted")
+            .conflicts_with("KeepQuotes")
+            .help("Also applies --trim inside quoted fields once they are unquoted")
+        )
+        .arg(
+            Arg::with_name("OutputDelimiter")
+            .long("output-delimiter")
+            .value_name("STRING")
+            .takes_value(true)
+            .help("String placed between selected fields, defaults to the input delimiter whether given or inferred")
+        )
+        .arg(
+            Arg::with_name("OutputFormat")
+            .long("output-format")
+            .value_name("FORMAT")
+            .takes_value(true)
+            .possible_values(&["plain", "csv", "json", "ndjson", "table", "markdown"])
+            .help("plain (default) joins fields with the output delimiter as they are, csv unquotes fields then quotes any containing the delimiter, a quote or a line break as in RFC 4180 with , as the default delimiter. json writes an array of records and ndjson a record per line, records are objects keyed by the header with -m or arrays otherwise. table pads each column to its widest field and markdown writes a pipe table, both are written once all the input is read")
+        )
+        .arg(
+            Arg::with_name("Missing")
+            .long("missing")
+            .value_name("POLICY")
+            .takes_value(true)
+            .validator(|x| parse_missing(&x).map(|_| ()))
+            .help("What to do when a line is too short for the list: error (default), skip-line, empty or fill=<text>")
+        )
+        .arg(
+            Arg::with_name("OutOfRange")
+            .long("out-of-range")
+            .value_name("POLICY")
+            .takes_value(true)
+            .possible_values(&["clamp", "missing"])
+            .help("How ranges reaching past the ends of a line are handled: clamp (default) stops them at the line, missing applies the missing policy to each position past the line")
+        )
+        .arg(
+            Arg::with_name("Wide")
+            .long("wide")
+            .value_name("POLICY")
+            .takes_value(true)
+            .possible_values(&["include", "exclude", "pad"])
+            .requires("Columns")
+            .help("What to do with a wide character cut in half by a -w range: include it, exclude it or pad (default) with a space")
+        )
+        .arg(
+            Arg::with_name("SampleLines")
+            .long("sample")
+            .value_name("LINES")
+            .takes_value(true)
+            .validator(|x| x.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+            .help("Number of lines from the start of the input used to infer the delimiter, defaults to 10")
+        )
+        .arg(
+            Arg::with_name("Explain")
+            .long("explain")
+            .help("Prints how the cut resolves against the first line, the delimiter and why it was chosen, the parsed list, header matches and the indices selected, then exits without cutting")
+        )
         .arg(
             Arg::with_name("NoMultiByteSplit")
             .short("n")
@@ -93,16 +232,26 @@ fn main() {
         .or_else(|| {
             matches
                 .value_of("Characters")
-                .map(|x| CutType::Characters(x))
+                .map(CutType::Characters)
+        })
+        .or_else(|| {
+            matches
+                .value_of("Graphemes")
+                .map(CutType::Graphemes)
+        })
+        .or_else(|| {
+            matches
+                .value_of("Columns")
+                .map(CutType::Columns)
         })
         .or_else(|| {
             matches
                 .value_of("Fields")
-                .map(|x| CutType::FieldsInferDelimiter(x))
+                .map(CutType::FieldsInferDelimiter)
         })
         .or_else(||{
             matches.value_of("MatchField")
-            .map(|x| CutType::MatchesInferDelimiter(x))
+            .map(CutType::MatchesInferDelimiter)
         })
         .unwrap();
 
@@ -112,6 +261,10 @@ fn main() {
                 CutType::FieldsRegexDelimiter(RangeDelimiter::new(x, s))
             } else if let Some(s) = matches.value_of("Split") {
                 CutType::FieldsStringDelimiter(RangeDelimiter::new(x, s))
+            } else if let Some(s) = matches.value_of("Widths") {
+                CutType::FieldsFixedWidth(RangeDelimiter::new(x, s))
+            } else if matches.is_present("Aligned") {
+                CutType::FieldsInferColumns(x)
             } else {
                 CutType::FieldsInferDelimiter(x)
             }
@@ -121,6 +274,10 @@ fn main() {
                 CutType::MatchesRegexDelimiter(RangeDelimiter::new(x, s))
             } else if let Some(s) = matches.value_of("Split") {
                 CutType::MatchesStringDelimiter(RangeDelimiter::new(x, s))
+            } else if let Some(s) = matches.value_of("Widths") {
+                CutType::MatchesFixedWidth(RangeDelimiter::new(x, s))
+            } else if matches.is_present("Aligned") {
+                CutType::MatchesInferColumns(x)
             } else {
                 CutType::MatchesInferDelimiter(x)
             }
@@ -128,15 +285,116 @@ fn main() {
         x => x,
     };
 
-    println!("{:?} ", cut_type);
+    let output_format = matches.value_of("OutputFormat").unwrap_or("plain");
 
-    cut(input_type,cut_type).unwrap();
+    let defaults = CutOptions::default();
+    let options = CutOptions {
+        missing: matches
+            .value_of("Missing")
+            .map(|x| parse_missing(x).unwrap())
+            .unwrap_or_default(),
+        out_of_range: match matches.value_of("OutOfRange") {
+            Some("missing") => OutOfRange::Missing,
+            _ => OutOfRange::Clamp,
+        },
+        output_delimiter: matches
+            .value_of("OutputDelimiter")
+            .map(String::from)
+            .or_else(|| match output_format {
+                // fields are separated by commas whatever they were split by
+                "csv" if matches.is_present("DataSeperator") => Some(String::from(",")),
+                _ => None,
+            }),
+        wide: match matches.value_of("Wide") {
+            Some("include") => WidePolicy::Include,
+            Some("exclude") => WidePolicy::Exclude,
+            _ => WidePolicy::Pad,
+        },
+        sample_lines: matches
+            .value_of("SampleLines")
+            .map_or(defaults.sample_lines, |x| x.parse().unwrap()),
+        width_unit: match matches.value_of("WidthsIn") {
+            Some("columns") => WidthUnit::Columns,
+            _ => WidthUnit::Characters,
+        },
+        // fields are quoted again by formats other than plain
+        unquote: !matches.is_present("KeepQuotes") || output_format != "plain",
+        quote: matches.value_of("Quote").and_then(|x| x.chars().next()),
+        escape: matches.value_of("Escape").and_then(|x| x.chars().next()),
+        trim: match matches.value_of("Trim") {
+            Some("none") => TrimPolicy::None,
+            Some("left") => TrimPolicy::Left,
+            Some("right") => TrimPolicy::Right,
+            _ => TrimPolicy::Both,
+        },
+        trim_quoted: matches.is_present("TrimQuoted"),
+    };
+
+    if matches.is_present("Explain") {
+        match explain(input_type, cut_type, &options) {
+            Ok(explanation) => print!("{}", explanation),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let stdout = stdout();
+    let mut sink: Box<dyn RecordSink> = match output_format {
+        "csv" => Box::new(CsvSink::new(stdout.lock())),
+        "json" => Box::new(JsonSink::new(stdout.lock())),
+        "ndjson" => Box::new(JsonSink::ndjson(stdout.lock())),
+        "table" => Box::new(TableSink::new(stdout.lock())),
+        "markdown" => Box::new(TableSink::markdown(stdout.lock())),
+        _ => Box::new(WriteSink::new(stdout.lock())),
+    };
+
+    if let Err(e) = cut(input_type, cut_type, &options, sink.as_mut()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_missing(input: &str) -> Result<MissingPolicy, String> {
+    match input {
+        "error" => Ok(MissingPolicy::Error),
+        "skip-line" => Ok(MissingPolicy::SkipLine),
+        "empty" => Ok(MissingPolicy::Empty),
+        _ if input.starts_with("fill=") => Ok(MissingPolicy::Fill(input["fill=".len()..].to_owned())),
+        _ => Err(String::from("Missing policy must be one of error, skip-line, empty or fill=<text>")),
+    }
 }
+fn check_char(input: String) -> Result<(), String> {
+    if input.chars().count() == 1 {
+        Ok(())
+    } else {
+        Err(String::from("Must be a single character"))
+    }
+}
+
+/**
+Widths are whole numbers separated by commas, optionally ending in * for the rest of the line
+*/
+fn check_widths(input: String) -> Result<(), String> {
+    // as in FixedWidths::parse * can only be the last width
+    let mut widths = input.split(',').map(str::trim).collect::<Vec<_>>();
+    if widths.last() == Some(&"*") {
+        widths.pop();
+    }
+    if widths.iter().all(|width| width.parse::<usize>().is_ok()) {
+        Ok(())
+    } else {
+        Err(String::from("Widths must be whole numbers separated by commas, optionally ending in * e.g 8,12,5,*"))
+    }
+}
+
 /**
 Uses a few regexes to get rid of the most obvious errors full parsing done later
 */
 fn check_formatted_lists(input:String)-> Result<(),String>{
-    let fail_conditions = RegexSet::new(&[
+    let fail_conditions = RegexSet::new([
         r"[^-:,\d]",
         r"-\D",
         r"(-|,)$",
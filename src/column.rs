@@ -0,0 +1,106 @@
+use crate::WidePolicy;
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/**
+One terminal column of a line. Wide characters cover two columns so they appear twice,
+once with offset 0 and once with offset 1
+*/
+#[derive(Clone, Debug)]
+pub struct Column<'l> {
+    cell: usize,
+    text: Cow<'l, str>,
+    width: usize,
+    offset: usize,
+}
+
+impl<'l> Column<'l> {
+    /**
+    Text that always takes up its whole width, used when filling in missing columns
+    */
+    pub fn fill(text: &str) -> Vec<Column<'l>> {
+        vec![Column {
+            cell: usize::MAX,
+            text: Cow::Owned(text.to_owned()),
+            width: 1,
+            offset: 0,
+        }]
+    }
+}
+
+/**
+Splits a line into the terminal columns it is displayed in, using the East Asian Width of each
+grapheme cluster. Zero width clusters such as control characters take no columns and are dropped
+*/
+pub fn display_columns(line: &str) -> Vec<Column<'_>> {
+    line.graphemes(true)
+        .enumerate()
+        .flat_map(|(cell, grapheme)| {
+            // clusters such as emoji with modifiers sum wider than they are displayed
+            let width = grapheme.width().min(2);
+            (0..width).map(move |offset| Column {
+                cell,
+                text: Cow::Borrowed(grapheme),
+                width,
+                offset,
+            })
+        })
+        .collect()
+}
+
+/**
+Joins each group of selected columns back into text. A wide character with only one of its columns
+selected straddles the edge of the selection and is handled by the wide policy, unless its other
+column starts the next group, in which case the whole character goes to the first group
+*/
+pub fn render_groups(groups: &[Vec<Column>], wide: &WidePolicy) -> Vec<String> {
+    let mut rendered = Vec::with_capacity(groups.len());
+    let mut joined = false;
+
+    for (i, group) in groups.iter().enumerate() {
+        // the column already rendered with the end of the group before
+        let columns = if joined { &group[1..] } else { &group[..] };
+        let after = groups.get(i + 1).and_then(|next| next.first());
+        let (text, joins_next) = render_columns(columns, after, wide);
+        rendered.push(text);
+        joined = joins_next;
+    }
+
+    rendered
+}
+
+/**
+Joins selected columns back into text, `after` is the column selected straight after them.
+Also returns whether a wide character was completed by `after`
+*/
+fn render_columns(columns: &[Column], after: Option<&Column>, wide: &WidePolicy) -> (String, bool) {
+    let mut rendered = String::new();
+    let mut joins_after = false;
+    let mut i = 0;
+
+    while i < columns.len() {
+        let column = &columns[i];
+        let whole = column.width < 2
+            || (column.offset == 0
+                && columns
+                    .get(i + 1)
+                    .or(after)
+                    .is_some_and(|next| next.cell == column.cell && next.offset == 1));
+
+        if whole {
+            rendered.push_str(&column.text);
+            joins_after = column.width == 2 && i + 1 == columns.len();
+            i += column.width.max(1);
+        } else {
+            match wide {
+                WidePolicy::Include => rendered.push_str(&column.text),
+                WidePolicy::Exclude => {}
+                WidePolicy::Pad => rendered.push(' '),
+            }
+            i += 1;
+        }
+    }
+
+    (rendered, joins_after)
+}
@@ -0,0 +1,1048 @@
+use crate::column::{display_columns, render_groups, Column};
+use crate::error::{MissingFieldError, RecutError};
+use crate::explain::Explanation;
+use crate::field::{
+    split_line_quotes, split_line_regex_quotes, trim_field, unquote, DelimiterType, Quoting,
+    RecordLines,
+};
+use crate::fixed::FixedWidths;
+use crate::infer::{infer_columns, infer_delimiter, infer_quote, DelimiterCandidate, Inference};
+use crate::match_field::{explain_matches, match_header};
+use crate::range::{expand_index, expand_indices, parse_indices, UnExpandedIndices};
+use crate::{CutOptions, CutType, MissingPolicy};
+use regex::Regex;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    convert::TryFrom,
+    io::{self, BufRead, Lines, Split},
+    iter::Chain,
+    vec,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/**
+How a line is broken up into the pieces that indices refer to
+*/
+enum Splitter {
+    Bytes(bool),
+    /// Unicode scalar values, so a combining mark is a character of its own
+    Characters,
+    /// Extended grapheme clusters, what a reader sees as a single character
+    Graphemes,
+    /// Terminal display columns, wide characters cover two
+    Columns,
+    String(String),
+    Regex(Regex),
+    /// Fields at fixed character offsets
+    Fixed(FixedWidths),
+}
+
+/**
+Which pieces of a split line are kept and in what order
+*/
+enum Selection {
+    Indices(Vec<UnExpandedIndices>),
+    Matches(Vec<usize>),
+}
+
+impl Selection {
+    /**
+    Picks the selected pieces grouped by the list entry that selected them. Indices past the end
+    of the line are handled by the missing policy with `fill` converting the fill text into pieces,
+    stopping at the first one when it is an error or skips the line. A range that is backwards on
+    this line selects nothing from it and is handled by the missing policy the same way.
+    `None` means the line should be skipped
+    */
+    fn select<T: Clone>(
+        &self,
+        pieces: &[T],
+        options: &CutOptions,
+        fill: impl Fn(&str) -> Vec<T>,
+    ) -> Result<Option<Vec<Vec<T>>>, RecutError> {
+        let missing = &options.missing;
+        let groups = match self {
+            Selection::Indices(indices) => indices
+                .iter()
+                .map(|entry| expand_index(entry, pieces.len(), &options.out_of_range))
+                .collect::<Vec<_>>(),
+            Selection::Matches(indices) => {
+                indices.iter().map(|&i| Ok(i as i64..=i as i64)).collect()
+            }
+        };
+
+        let mut selected = Vec::with_capacity(groups.len());
+        for group in groups {
+            let group = match (group, missing) {
+                (Ok(group), _) => group,
+                (Err(e), MissingPolicy::Error) => return Err(e.into()),
+                (Err(_), MissingPolicy::SkipLine) => return Ok(None),
+                (Err(_), MissingPolicy::Empty) => {
+                    selected.push(fill(""));
+                    continue;
+                }
+                (Err(_), MissingPolicy::Fill(text)) => {
+                    selected.push(fill(text));
+                    continue;
+                }
+            };
+            let mut selected_group = Vec::new();
+            for i in group {
+                let piece = usize::try_from(i).ok().and_then(|i| pieces.get(i));
+                match (piece, missing) {
+                    (Some(piece), _) => selected_group.push(piece.clone()),
+                    (None, MissingPolicy::Error) => {
+                        return Err(MissingFieldError::new(i, pieces.len()).into())
+                    }
+                    (None, MissingPolicy::SkipLine) => return Ok(None),
+                    (None, MissingPolicy::Empty) => selected_group.append(&mut fill("")),
+                    (None, MissingPolicy::Fill(text)) => selected_group.append(&mut fill(text)),
+                }
+            }
+            selected.push(selected_group);
+        }
+
+        Ok(Some(selected))
+    }
+
+    /**
+    Every selected piece as its own field
+    */
+    fn select_fields<'l>(
+        &self,
+        pieces: &[Cow<'l, str>],
+        options: &CutOptions,
+    ) -> Result<Option<Vec<Cow<'l, str>>>, RecutError> {
+        let selected = self.select(pieces, options, |text| vec![Cow::Owned(text.to_owned())])?;
+        Ok(selected.map(|groups| groups.into_iter().flatten().collect()))
+    }
+}
+
+/**
+Selects the requested parts of each line for a cut type.
+Inferred delimiters and matched fields are only known once the first line has been seen,
+until then the cutter is unresolved and `resolve` must be called with the first line
+*/
+pub struct Cutter<'a> {
+    cut_type: CutType<'a>,
+    options: CutOptions,
+    splitter: Option<Splitter>,
+    selection: Option<Selection>,
+    output_delimiter: Option<String>,
+    inference: Option<Inference>,
+    quoting: Option<Quoting>,
+}
+
+impl<'a> Cutter<'a> {
+    /**
+    Parses the list and delimiter of the cut type, anything depending on the input is left for `resolve`
+    */
+    pub fn new(cut_type: CutType<'a>, options: &CutOptions) -> Result<Cutter<'a>, RecutError> {
+        let (splitter, selection) = match &cut_type {
+            CutType::Bytes(range, split) => (
+                Some(Splitter::Bytes(*split)),
+                Some(Selection::Indices(parse_indices(range)?)),
+            ),
+            CutType::Characters(range) => (
+                Some(Splitter::Characters),
+                Some(Selection::Indices(parse_indices(range)?)),
+            ),
+            CutType::Graphemes(range) => (
+                Some(Splitter::Graphemes),
+                Some(Selection::Indices(parse_indices(range)?)),
+            ),
+            CutType::Columns(range) => (
+                Some(Splitter::Columns),
+                Some(Selection::Indices(parse_indices(range)?)),
+            ),
+            CutType::FieldsInferDelimiter(range) => {
+                (None, Some(Selection::Indices(parse_indices(range)?)))
+            }
+            CutType::FieldsRegexDelimiter(range) => (
+                Some(Splitter::Regex(Regex::new(range.delimiter)?)),
+                Some(Selection::Indices(parse_indices(range.locations)?)),
+            ),
+            CutType::FieldsStringDelimiter(range) => (
+                Some(Splitter::String(range.delimiter.to_owned())),
+                Some(Selection::Indices(parse_indices(range.locations)?)),
+            ),
+            CutType::FieldsInferColumns(range) => {
+                (None, Some(Selection::Indices(parse_indices(range)?)))
+            }
+            CutType::FieldsFixedWidth(range) => (
+                Some(Splitter::Fixed(FixedWidths::parse(
+                    range.delimiter,
+                    options.width_unit.clone(),
+                )?)),
+                Some(Selection::Indices(parse_indices(range.locations)?)),
+            ),
+            CutType::MatchesFixedWidth(range) => (
+                Some(Splitter::Fixed(FixedWidths::parse(
+                    range.delimiter,
+                    options.width_unit.clone(),
+                )?)),
+                None,
+            ),
+            CutType::MatchesInferDelimiter(_) | CutType::MatchesInferColumns(_) => (None, None),
+            CutType::MatchesRegexDelimiter(range) => {
+                (Some(Splitter::Regex(Regex::new(range.delimiter)?)), None)
+            }
+            CutType::MatchesStringDelimiter(range) => {
+                (Some(Splitter::String(range.delimiter.to_owned())), None)
+            }
+        };
+
+        let output_delimiter = match (&options.output_delimiter, &splitter) {
+            (Some(output_delimiter), _) => Some(output_delimiter.to_owned()),
+            (None, Some(Splitter::Bytes(_)))
+            | (None, Some(Splitter::Characters))
+            | (None, Some(Splitter::Graphemes))
+            | (None, Some(Splitter::Columns)) => Some(String::new()),
+            (None, Some(Splitter::String(delimiter))) => Some(delimiter.to_owned()),
+            (None, Some(Splitter::Fixed(_))) => Some(String::from(" ")),
+            (None, _) => None,
+        };
+
+        Ok(Cutter {
+            cut_type,
+            options: options.clone(),
+            splitter,
+            selection,
+            output_delimiter,
+            inference: None,
+            quoting: options
+                .quote
+                .map(|quote| Quoting::new(quote, options.escape)),
+        })
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.splitter.is_some()
+            && self.selection.is_some()
+            && self.output_delimiter.is_some()
+            && (self.quoting.is_some() || !self.quoted_records())
+    }
+
+    /**
+    The text placed between selected fields on output. Unless given in the options
+    this is the input delimiter, for regex delimiters it is whatever the regex matched on the first line
+    */
+    pub fn output_delimiter(&self) -> &str {
+        self.output_delimiter.as_deref().unwrap_or("")
+    }
+
+    /**
+    Every candidate considered when the delimiter was inferred, best first.
+    Empty when the delimiter was given or has not been inferred yet
+    */
+    pub fn delimiter_ranking(&self) -> &[DelimiterCandidate] {
+        self.inference
+            .as_ref()
+            .map_or(&[], |inference| inference.ranking.as_slice())
+    }
+
+    /**
+    Whether quoted fields are recognised, in which case a record can span several lines
+    */
+    pub fn quoted_records(&self) -> bool {
+        matches!(
+            self.cut_type,
+            CutType::FieldsInferDelimiter(_)
+                | CutType::FieldsRegexDelimiter(_)
+                | CutType::FieldsStringDelimiter(_)
+                | CutType::MatchesInferDelimiter(_)
+                | CutType::MatchesRegexDelimiter(_)
+                | CutType::MatchesStringDelimiter(_)
+        )
+    }
+
+    /**
+    The quoting fields are split with, `"` doubled as in CSV until it is given or inferred
+    */
+    fn quoting(&self) -> &Quoting {
+        const DEFAULT_QUOTING: &Quoting = &Quoting {
+            quote: '"',
+            escape: '"',
+        };
+        self.quoting.as_ref().unwrap_or(DEFAULT_QUOTING)
+    }
+
+    /**
+    Reads the records of an input. So that lines can be joined into records the quote, when not given,
+    is inferred from the first lines and so is the delimiter, from the records those lines would be
+    joined into if any punctuation or whitespace could start a quoted field
+    */
+    fn record_lines<R: BufRead>(
+        &mut self,
+        input: R,
+    ) -> Result<RecordLines<SampledLines<R>>, RecutError> {
+        // the quote is inferred from the sample even when the delimiter is already known
+        let sample_size = if self.quoted_records() && self.quoting.is_none() {
+            self.options.sample_lines.max(1)
+        } else {
+            self.sample_size()
+        };
+        let mut lines = input.lines();
+        let first_lines = lines
+            .by_ref()
+            .take(sample_size)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let quoting = if self.quoted_records() {
+            if self.quoting.is_none() {
+                self.quoting = Some(Quoting::new(infer_quote(&first_lines), self.options.escape));
+            }
+            self.quoting.clone()
+        } else {
+            None
+        };
+        if quoting.is_some() && self.splitter.is_none() && !first_lines.is_empty() {
+            // a record left open at the end of the sample is cut off by the sample, not the input
+            let sample = RecordLines::new(
+                first_lines.iter().cloned().map(Ok),
+                quoting.clone(),
+                DelimiterType::Unknown,
+                self.options.trim.clone(),
+            )
+            .filter_map(|record| record.ok().map(|(_, record)| record))
+            .collect::<Vec<_>>();
+            self.infer_splitter(&sample)?;
+        }
+
+        let first_lines = first_lines.into_iter().map(Ok).collect::<Vec<_>>();
+        Ok(RecordLines::new(
+            first_lines.into_iter().chain(lines),
+            quoting,
+            self.field_delimiter(),
+            self.options.trim.clone(),
+        ))
+    }
+
+    /**
+    The delimiter quoted fields are recognised after, `Unknown` until there is one
+    */
+    fn field_delimiter(&self) -> DelimiterType<'static> {
+        match &self.splitter {
+            Some(Splitter::String(delimiter)) => {
+                DelimiterType::String(Cow::Owned(delimiter.clone()))
+            }
+            Some(Splitter::Regex(delimiter)) => DelimiterType::Regex(Cow::Owned(delimiter.clone())),
+            _ => DelimiterType::Unknown,
+        }
+    }
+
+    /**
+    Infers the fixed width columns or the delimiter from the sampled lines unless a splitter is known
+    */
+    fn infer_splitter(&mut self, sample: &[String]) -> Result<(), RecutError> {
+        if self.splitter.is_some() {
+            return Ok(());
+        }
+        let infer_columns_type = matches!(
+            self.cut_type,
+            CutType::FieldsInferColumns(_) | CutType::MatchesInferColumns(_)
+        );
+        if infer_columns_type {
+            self.splitter = Some(Splitter::Fixed(infer_columns(sample)?));
+            return Ok(());
+        }
+
+        let inference = infer_delimiter(sample, self.quoting())?;
+        self.splitter = Some(if inference.regex {
+            Splitter::Regex(Regex::new(&inference.delimiter)?)
+        } else {
+            Splitter::String(inference.delimiter.to_owned())
+        });
+        self.inference = Some(inference);
+        Ok(())
+    }
+
+    /**
+    How many lines from the start of the input `resolve` needs to see
+    */
+    pub fn sample_size(&self) -> usize {
+        if self.splitter.is_none() {
+            self.options.sample_lines.max(1)
+        } else {
+            1
+        }
+    }
+
+    /**
+    Infers the delimiter from the sampled lines and matches fields against the first line.
+    For match cut types the first line is a header and the matched header fields are returned.
+    Fails with `NoInputError` when there are no lines but the delimiter or header has to be inferred,
+    without a first line a regex delimiter is output as a space
+    */
+    pub fn resolve(&mut self, sample: &[String]) -> Result<Option<Vec<String>>, RecutError> {
+        if sample.is_empty() && (self.splitter.is_none() || self.selection.is_none()) {
+            return Err(RecutError::NoInputError);
+        }
+        let first_line = sample.first().map_or("", |line| line.as_str());
+
+        if self.quoting.is_none() && self.quoted_records() {
+            self.quoting = Some(Quoting::new(infer_quote(sample), self.options.escape));
+        }
+        self.infer_splitter(sample)?;
+        if self.output_delimiter.is_none() {
+            self.output_delimiter = match &self.splitter {
+                Some(Splitter::String(delimiter)) => Some(delimiter.to_owned()),
+                Some(Splitter::Regex(_)) if self.inference.is_some() => Some(String::from(" ")),
+                Some(Splitter::Fixed(_)) => Some(String::from(" ")),
+                Some(Splitter::Regex(delimiter)) => Some(
+                    delimiter
+                        .find(first_line)
+                        .map_or(" ", |found| found.as_str())
+                        .to_owned(),
+                ),
+                _ => Some(String::new()),
+            };
+        }
+        if self.selection.is_some() {
+            return Ok(None);
+        }
+        let match_str = match &self.cut_type {
+            CutType::MatchesInferDelimiter(match_str) | CutType::MatchesInferColumns(match_str) => {
+                match_str
+            }
+            CutType::MatchesRegexDelimiter(range)
+            | CutType::MatchesStringDelimiter(range)
+            | CutType::MatchesFixedWidth(range) => range.locations,
+            _ => unreachable!(), //every other cut type has its selection from the list
+        };
+
+        let header = self
+            .split_fields(first_line)
+            .expect("matches always split into fields");
+        let (header, indices) = match_header(match_str, &header)?;
+        self.selection = Some(Selection::Matches(indices));
+
+        Ok(Some(header))
+    }
+
+    /**
+    Splits a line into fields for splitters with a delimiter or fixed widths, `None` for the others.
+    Column aligned input inferred to split on runs of whitespace is often indented,
+    so the line is trimmed to not start with an empty field
+    */
+    fn split_fields<'l>(&self, line: &'l str) -> Option<Vec<Cow<'l, str>>> {
+        let quoting = self.quoting();
+        let trim = &self.options.trim;
+        let fields = match self.splitter.as_ref()? {
+            Splitter::String(delimiter) => split_line_quotes(line, delimiter, quoting, trim),
+            Splitter::Regex(delimiter) if self.inference.is_some() => {
+                split_line_regex_quotes(line.trim(), delimiter, quoting, trim)
+            }
+            Splitter::Regex(delimiter) => split_line_regex_quotes(line, delimiter, quoting, trim),
+            Splitter::Fixed(widths) => widths.split(line, trim),
+            _ => return None,
+        };
+
+        Some(
+            fields
+                .into_iter()
+                .map(|field| {
+                    if !self.options.unquote {
+                        return Cow::Borrowed(field);
+                    }
+                    match unquote(field, quoting) {
+                        Cow::Borrowed(unquoted) if self.options.trim_quoted => {
+                            Cow::Borrowed(trim_field(unquoted, trim))
+                        }
+                        Cow::Owned(unquoted) if self.options.trim_quoted => {
+                            Cow::Owned(trim_field(&unquoted, trim).to_owned())
+                        }
+                        unquoted => unquoted,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /**
+    Selects from a single line, the cutter must already be resolved.
+    Returns `None` when the line is skipped because of the missing policy
+    */
+    pub fn cut_line<'l>(&self, line: &'l str) -> Result<Option<Vec<Cow<'l, str>>>, RecutError> {
+        let selection = self.selection.as_ref().expect("cutter must be resolved");
+        let options = &self.options;
+
+        let selected = match self.splitter.as_ref().expect("cutter must be resolved") {
+            Splitter::Bytes(_) => self.cut_bytes(line.as_bytes())?.map(|selected| {
+                selected
+                    .iter()
+                    .map(|bytes| Cow::Owned(String::from_utf8_lossy(bytes).into_owned()))
+                    .collect()
+            }),
+            Splitter::Characters => {
+                // indexed by position in the line, not the byte offset char_indices would give
+                let chars = line.chars().collect::<Vec<_>>();
+                selection
+                    .select(&chars, options, |text| text.chars().collect())?
+                    .map(|groups| {
+                        groups
+                            .into_iter()
+                            .map(|group| Cow::Owned(group.into_iter().collect()))
+                            .collect()
+                    })
+            }
+            Splitter::Graphemes => {
+                let graphemes = line.graphemes(true).map(Cow::Borrowed).collect::<Vec<_>>();
+                selection
+                    .select(&graphemes, options, |text| {
+                        vec![Cow::Owned(text.to_owned())]
+                    })?
+                    .map(|groups| {
+                        groups
+                            .into_iter()
+                            .map(|group| Cow::Owned(group.concat()))
+                            .collect()
+                    })
+            }
+            Splitter::Columns => {
+                let columns = display_columns(line);
+                selection
+                    .select(&columns, options, Column::fill)?
+                    .map(|groups| {
+                        render_groups(&groups, &options.wide)
+                            .into_iter()
+                            .map(Cow::Owned)
+                            .collect()
+                    })
+            }
+            Splitter::String(_) | Splitter::Regex(_) | Splitter::Fixed(_) => {
+                let split_line = self
+                    .split_fields(line)
+                    .expect("fields split by a delimiter");
+                selection.select_fields(&split_line, options)?
+            }
+        };
+
+        Ok(selected)
+    }
+
+    /**
+    Selects from a line of raw bytes for the bytes cut type, the selected bytes are left untouched
+    apart from trimming partial UTF-8 sequences when splits are not allowed
+    */
+    pub fn cut_bytes<'l>(&self, line: &'l [u8]) -> Result<Option<Vec<Cow<'l, [u8]>>>, RecutError> {
+        let splits_allowed = match self.splitter {
+            Some(Splitter::Bytes(splits_allowed)) => splits_allowed,
+            _ => return Err(RecutError::NotBytesError),
+        };
+        let selection = self.selection.as_ref().expect("cutter must be resolved");
+
+        let selected = selection.select(line, &self.options, |text| text.as_bytes().to_vec())?;
+        Ok(selected.map(|groups| {
+            groups
+                .into_iter()
+                .map(|group| {
+                    if splits_allowed {
+                        Cow::Owned(group)
+                    } else {
+                        Cow::Owned(trim_partial_utf8(&group).to_vec())
+                    }
+                })
+                .collect()
+        }))
+    }
+
+    /**
+    Like `records` but cuts lines of raw bytes, so input does not need to be UTF-8.
+    Only the bytes cut type can be used, any other fails with `NotBytesError`
+    */
+    pub fn byte_records<R: BufRead>(self, input: R) -> Result<ByteRecords<'a, R>, RecutError> {
+        if !matches!(self.cut_type, CutType::Bytes(..)) {
+            return Err(RecutError::NotBytesError);
+        }
+        Ok(ByteRecords {
+            cutter: self,
+            lines: input.split(b'\n'),
+            line_number: 0,
+        })
+    }
+
+    /**
+    Consumes the cutter, resolving it against the first line of the input
+    and describing what it resolved to instead of cutting
+    */
+    pub fn explain<R: BufRead>(mut self, input: R) -> Result<Explanation, RecutError> {
+        let (raw_line, sample) = if self.quoted_records() {
+            let sample_size = self.sample_size();
+            let sample = self
+                .record_lines(input)?
+                .take(sample_size)
+                .map(|record| record.map(|(_, record)| record))
+                .collect::<Result<Vec<_>, _>>()?;
+            (vec![], sample)
+        } else {
+            let raw_sample = input
+                .split(b'\n')
+                .take(self.sample_size())
+                .map(|line| {
+                    line.map(|mut line| {
+                        if line.last() == Some(&b'\r') {
+                            line.pop();
+                        }
+                        line
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let sample = raw_sample
+                .iter()
+                .map(|line| String::from_utf8_lossy(line).into_owned())
+                .collect::<Vec<_>>();
+            (raw_sample.into_iter().next().unwrap_or_default(), sample)
+        };
+        let first_line = sample.first().map_or("", |line| line.as_str());
+
+        self.resolve(&sample)?;
+
+        let delimiter_reason = self
+            .inference
+            .as_ref()
+            .map(|inference| format!("inferred, {}", inference.reason))
+            .or_else(|| match &self.cut_type {
+                CutType::FieldsRegexDelimiter(_) | CutType::MatchesRegexDelimiter(_) => {
+                    Some(String::from("given as a regex with -d"))
+                }
+                CutType::FieldsStringDelimiter(_) | CutType::MatchesStringDelimiter(_) => {
+                    Some(String::from("given as a string with -s"))
+                }
+                CutType::FieldsFixedWidth(_) | CutType::MatchesFixedWidth(_) => {
+                    Some(String::from("given as widths with --widths"))
+                }
+                CutType::FieldsInferColumns(_) | CutType::MatchesInferColumns(_) => Some(format!(
+                    "inferred from whitespace lined up across {} sampled lines",
+                    sample.len()
+                )),
+                _ => None,
+            });
+
+        let delimiter = match &self.splitter {
+            Some(Splitter::String(delimiter)) => Some(format!("{:?}", delimiter)),
+            Some(Splitter::Regex(delimiter)) => Some(format!("/{}/", delimiter.as_str())),
+            Some(Splitter::Fixed(widths)) => Some(format!("fixed widths at {}", widths.describe())),
+            _ => None,
+        };
+        let fields = self.split_fields(first_line);
+
+        let first_line_length = match (&self.splitter, &fields) {
+            (_, Some(fields)) => fields.len(),
+            (Some(Splitter::Bytes(_)), _) => raw_line.len(),
+            (Some(Splitter::Characters), _) => first_line.chars().count(),
+            (Some(Splitter::Graphemes), _) => first_line.graphemes(true).count(),
+            _ => display_columns(first_line).len(),
+        };
+
+        let (indices, first_line_indices) = match self.selection.as_ref() {
+            Some(Selection::Indices(indices)) => (
+                indices.iter().map(|index| format!("{:?}", index)).collect(),
+                expand_indices(indices, first_line_length, &self.options.out_of_range)
+                    .map_err(|e| e.to_string()),
+            ),
+            Some(Selection::Matches(indices)) => (
+                vec![],
+                Ok(indices.iter().map(|&i| i as i64..=i as i64).collect()),
+            ),
+            None => (vec![], Ok(vec![])),
+        };
+
+        let matches = match (&self.cut_type, &fields) {
+            (CutType::MatchesInferDelimiter(match_str), Some(fields))
+            | (CutType::MatchesInferColumns(match_str), Some(fields)) => {
+                explain_matches(match_str, fields)?
+            }
+            (CutType::MatchesRegexDelimiter(range), Some(fields))
+            | (CutType::MatchesStringDelimiter(range), Some(fields))
+            | (CutType::MatchesFixedWidth(range), Some(fields)) => {
+                explain_matches(range.locations, fields)?
+            }
+            _ => vec![],
+        };
+
+        Ok(Explanation {
+            cut_type: format!("{:?}", self.cut_type),
+            delimiter,
+            delimiter_reason,
+            delimiter_ranking: self.delimiter_ranking().to_vec(),
+            quoting: self.quoting.as_ref().map(|quoting| {
+                let given = if self.options.quote.is_some() {
+                    "given with --quote"
+                } else {
+                    "inferred"
+                };
+                if quoting.escape == quoting.quote {
+                    format!("{:?} doubled to escape it, {}", quoting.quote, given)
+                } else {
+                    format!(
+                        "{:?} escaped with {:?}, {}",
+                        quoting.quote, quoting.escape, given
+                    )
+                }
+            }),
+            output_delimiter: self.output_delimiter().to_owned(),
+            indices,
+            matches,
+            first_line_length,
+            first_line_indices,
+        })
+    }
+
+    /**
+    Consumes the cutter, resolving it against the first lines of the input.
+    The sampled lines are replayed and the rest of the input is cut as the records are iterated
+    */
+    pub fn records<R: BufRead>(mut self, input: R) -> Result<Records<'a, R>, RecutError> {
+        let mut lines = self.record_lines(input)?;
+        let sample = lines
+            .by_ref()
+            .take(self.sample_size())
+            .collect::<Result<Vec<_>, _>>()?;
+        let (line_numbers, sample): (Vec<_>, Vec<_>) = sample.into_iter().unzip();
+
+        let mut header = None;
+        let mut buffered = VecDeque::with_capacity(sample.len());
+        if !sample.is_empty() || !self.is_resolved() {
+            header = self.resolve(&sample)?;
+            buffered.extend(line_numbers.into_iter().zip(sample));
+            if header.is_some() {
+                buffered.pop_front();
+            }
+        }
+
+        Ok(Records {
+            cutter: self,
+            lines,
+            line_number: header.iter().count(),
+            header,
+            buffered,
+        })
+    }
+}
+
+/**
+Lines read to sample the input followed by the rest of it
+*/
+type SampledLines<R> = Chain<vec::IntoIter<io::Result<String>>, Lines<R>>;
+
+/**
+Iterator over the selected records of an input, for match cut types the first record is the matched header.
+Lines sampled to resolve the cutter are buffered along with their line numbers until they are cut
+*/
+pub struct Records<'a, R: BufRead> {
+    cutter: Cutter<'a>,
+    lines: RecordLines<SampledLines<R>>,
+    header: Option<Vec<String>>,
+    buffered: VecDeque<(usize, String)>,
+    line_number: usize,
+}
+
+impl<'a, R: BufRead> Records<'a, R> {
+    pub fn cutter(&self) -> &Cutter<'a> {
+        &self.cutter
+    }
+
+    /**
+    Whether the next record is the matched header rather than one cut from a line
+    */
+    pub fn has_header(&self) -> bool {
+        self.header.is_some()
+    }
+
+    fn cut_line(&self, line: &str) -> Result<Option<Vec<Cow<'static, str>>>, RecutError> {
+        match self.cutter.cut_line(line) {
+            Ok(selected) => Ok(selected.map(|selected| {
+                selected
+                    .into_iter()
+                    .map(|field| Cow::Owned(field.into_owned()))
+                    .collect()
+            })),
+            Err(e) => Err(e.at_line(self.line_number)),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<'_, R> {
+    type Item = Result<Vec<Cow<'static, str>>, RecutError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(header) = self.header.take() {
+            return Some(Ok(header.into_iter().map(Cow::Owned).collect()));
+        }
+
+        loop {
+            let line = match self.buffered.pop_front() {
+                Some((line_number, line)) => {
+                    self.line_number = line_number;
+                    line
+                }
+                None => match self.lines.next()? {
+                    Ok((line_number, line)) => {
+                        self.line_number = line_number;
+                        line
+                    }
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+
+            match self.cut_line(&line) {
+                Ok(None) => {} //skipped line
+                selected => return selected.transpose(),
+            }
+        }
+    }
+}
+
+/**
+Iterator over the selected bytes of each line of an input
+*/
+pub struct ByteRecords<'a, R: BufRead> {
+    cutter: Cutter<'a>,
+    lines: Split<R>,
+    line_number: usize,
+}
+
+impl<'a, R: BufRead> ByteRecords<'a, R> {
+    pub fn cutter(&self) -> &Cutter<'a> {
+        &self.cutter
+    }
+}
+
+impl<R: BufRead> Iterator for ByteRecords<'_, R> {
+    type Item = Result<Vec<Cow<'static, [u8]>>, RecutError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line_number += 1;
+            // line endings are dropped the same way lines() does for text
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+
+            match self.cutter.cut_bytes(&line) {
+                Ok(Some(selected)) => {
+                    return Some(Ok(selected
+                        .into_iter()
+                        .map(|bytes| Cow::Owned(bytes.into_owned()))
+                        .collect()))
+                }
+                Ok(None) => {} //skipped line
+                Err(e) => return Some(Err(e.at_line(self.line_number))),
+            }
+        }
+    }
+}
+
+/**
+Removes continuation bytes from the start whose character began before the selection,
+and a character at the end missing some of its continuation bytes
+*/
+fn trim_partial_utf8(bytes: &[u8]) -> &[u8] {
+    let is_continuation = |byte: &u8| byte & 0b1100_0000 == 0b1000_0000;
+
+    let start = bytes
+        .iter()
+        .take(3)
+        .take_while(|b| is_continuation(b))
+        .count();
+    let bytes = &bytes[start..];
+
+    let trailing = bytes
+        .iter()
+        .rev()
+        .take(3)
+        .take_while(|b| is_continuation(b))
+        .count();
+    match bytes.len().checked_sub(trailing + 1) {
+        Some(lead_position) => {
+            let lead = bytes[lead_position];
+            let sequence_length = match lead {
+                0b1100_0000..=0b1101_1111 => 2,
+                0b1110_0000..=0b1110_1111 => 3,
+                0b1111_0000..=0b1111_0111 => 4,
+                _ => 1,
+            };
+            if sequence_length > trailing + 1 {
+                &bytes[..lead_position]
+            } else {
+                bytes
+            }
+        }
+        None => &bytes[..0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RangeDelimiter;
+
+    /**
+    What each entry of the list selects from a line
+    */
+    fn cut(cut_type: CutType, line: &str) -> Vec<String> {
+        let cutter = Cutter::new(cut_type, &CutOptions::default()).unwrap();
+        cutter
+            .cut_line(line)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect()
+    }
+
+    fn cut_characters(list: &str, line: &str) -> Vec<String> {
+        cut(CutType::Characters(list), line)
+    }
+
+    #[test]
+    fn characters_cjk() {
+        assert_eq!(cut_characters("1", "日本語"), ["本"]);
+        assert_eq!(cut_characters("-1", "日本語"), ["語"]);
+        assert_eq!(cut_characters("0:1,2", "日本語"), ["日本", "語"]);
+    }
+
+    #[test]
+    fn characters_emoji_with_skin_tone() {
+        // thumbs up followed by a medium skin tone modifier, two scalar values
+        let line = "\u{1F44D}\u{1F3FD}!";
+        assert_eq!(cut_characters("0", line), ["\u{1F44D}"]);
+        assert_eq!(cut_characters("1", line), ["\u{1F3FD}"]);
+        assert_eq!(cut_characters("0:1", line), ["\u{1F44D}\u{1F3FD}"]);
+        assert_eq!(cut_characters("-1", line), ["!"]);
+    }
+
+    #[test]
+    fn characters_combining_mark() {
+        let line = "e\u{301}x";
+        assert_eq!(cut_characters("0", line), ["e"]);
+        assert_eq!(cut_characters("1", line), ["\u{301}"]);
+        assert_eq!(cut_characters("0:1", line), ["e\u{301}"]);
+        assert_eq!(cut_characters("2", line), ["x"]);
+    }
+
+    #[test]
+    fn columns_wide_character_across_entries() {
+        assert_eq!(cut(CutType::Columns("0,1"), "日本"), ["日", ""]);
+        assert_eq!(cut(CutType::Columns("1,2"), "日本"), [" ", " "]);
+        assert_eq!(cut(CutType::Columns("0:2,3"), "日本"), ["日本", ""]);
+    }
+
+    #[test]
+    fn backwards_range_follows_missing_policy() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("2:-1", ","));
+        let cutter = |missing| {
+            let options = CutOptions {
+                missing,
+                ..CutOptions::default()
+            };
+            Cutter::new(cut_type.clone(), &options).unwrap()
+        };
+        // 2:-1 resolves to 2:1 on a line of two fields
+        assert!(cutter(MissingPolicy::Error).cut_line("x,y").is_err());
+        assert_eq!(
+            cutter(MissingPolicy::SkipLine).cut_line("x,y").unwrap(),
+            None
+        );
+        assert_eq!(
+            cutter(MissingPolicy::Fill(String::from("-")))
+                .cut_line("x,y")
+                .unwrap(),
+            Some(vec![Cow::Borrowed("-")])
+        );
+        assert_eq!(
+            cutter(MissingPolicy::SkipLine).cut_line("a,b,c").unwrap(),
+            Some(vec![Cow::Borrowed("c")])
+        );
+    }
+
+    #[test]
+    fn short_line_follows_missing_policy() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("0,2", ","));
+        let cutter = |missing| {
+            let options = CutOptions {
+                missing,
+                ..CutOptions::default()
+            };
+            Cutter::new(cut_type.clone(), &options).unwrap()
+        };
+        assert!(cutter(MissingPolicy::Error).cut_line("x,y").is_err());
+        assert_eq!(
+            cutter(MissingPolicy::SkipLine).cut_line("x,y").unwrap(),
+            None
+        );
+        assert_eq!(
+            cutter(MissingPolicy::Empty).cut_line("x,y").unwrap(),
+            Some(vec![Cow::Borrowed("x"), Cow::Borrowed("")])
+        );
+        assert_eq!(
+            cutter(MissingPolicy::Fill(String::from("-")))
+                .cut_line("x,y")
+                .unwrap(),
+            Some(vec![Cow::Borrowed("x"), Cow::Borrowed("-")])
+        );
+    }
+
+    #[test]
+    fn inference_errors() {
+        let cutter =
+            || Cutter::new(CutType::FieldsInferDelimiter("0"), &CutOptions::default()).unwrap();
+        assert!(matches!(
+            cutter().records(io::Cursor::new("")),
+            Err(RecutError::NoInputError)
+        ));
+        assert!(matches!(
+            cutter().records(io::Cursor::new("nodelimiter\n")),
+            Err(RecutError::DelimiterInferenceError(_))
+        ));
+    }
+
+    #[test]
+    fn trim_partial_utf8_at_the_start() {
+        // the last two bytes of 日 then a
+        assert_eq!(trim_partial_utf8(b"\x97\xa5a"), b"a");
+        assert_eq!(trim_partial_utf8("é日".as_bytes()), "é日".as_bytes());
+    }
+
+    #[test]
+    fn trim_partial_utf8_at_the_end() {
+        // 日 is three bytes and 👍 four, each missing its last byte
+        assert_eq!(trim_partial_utf8(b"a\xe6\x97"), b"a");
+        assert_eq!(trim_partial_utf8(b"a\xf0\x9f\x91"), b"a");
+        assert_eq!(trim_partial_utf8(b"a\xf0"), b"a");
+    }
+
+    #[test]
+    fn trim_partial_utf8_only_continuation_bytes() {
+        assert_eq!(trim_partial_utf8(b"\x97\xa5"), b"");
+        assert_eq!(trim_partial_utf8(b"\x9f\x91\x8d\x80"), b"");
+    }
+
+    #[test]
+    fn invalid_utf8_kept_when_splits_are_allowed() {
+        let cutter = Cutter::new(CutType::Bytes("0:2", true), &CutOptions::default()).unwrap();
+        let records = cutter
+            .byte_records(io::Cursor::new(b"\xff\xe6\x97\xa5\n\x97\xa5a\n"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, [[&b"\xff\xe6\x97"[..]], [&b"\x97\xa5a"[..]]]);
+    }
+
+    #[test]
+    fn quote_inferred_with_a_given_delimiter() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("0", ","));
+        let cutter = Cutter::new(cut_type, &CutOptions::default()).unwrap();
+        let records = cutter
+            .records(io::Cursor::new("'x,y',1\n'z,w',2\n"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, [["x,y"], ["z,w"]]);
+    }
+}
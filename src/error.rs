@@ -3,13 +3,21 @@ use fmt::Display;
 use pest::error::Error as PestError;
 use std::{error, fmt, io, num::ParseIntError};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum RecutError {
     IntError(ParseIntError),
     InputError(io::Error),
+    OutputError(io::Error),
     InputRangeParseError(PestError<range::Rule>),
     RegexError(regex::Error),
     RangeValueError(RangeError),
+    MissingFieldError(MissingFieldError),
+    LineRangeError(LineRangeError),
+    NoInputError,
+    NotBytesError,
+    DelimiterInferenceError(DelimiterInferenceError),
+    UnclosedQuoteError(UnclosedQuoteError),
 }
 
 impl fmt::Display for RecutError {
@@ -19,9 +27,41 @@ impl fmt::Display for RecutError {
             // This is a wrapper, so defer to the underlying types' implementation of `fmt`.
             RecutError::IntError(ref e) => e.fmt(f),
             RecutError::InputError(ref e) => e.fmt(f),
+            RecutError::OutputError(ref e) => e.fmt(f),
             RecutError::InputRangeParseError(ref e) => e.fmt(f),
             RecutError::RegexError(ref e) => e.fmt(f),
             RecutError::RangeValueError(ref e) => e.fmt(f),
+            RecutError::MissingFieldError(ref e) => e.fmt(f),
+            RecutError::LineRangeError(ref e) => e.fmt(f),
+            RecutError::NoInputError => write!(
+                f,
+                "The input is empty, the first line is needed to infer the delimiter or match the header"
+            ),
+            RecutError::NotBytesError => write!(
+                f,
+                "Only the bytes cut type can cut raw bytes, other cut types need the input as text"
+            ),
+            RecutError::DelimiterInferenceError(ref e) => e.fmt(f),
+            RecutError::UnclosedQuoteError(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl RecutError {
+    /**
+    Records which line of the input an error that depends on the line occurred on
+    */
+    pub fn at_line(self, line: usize) -> RecutError {
+        match self {
+            RecutError::MissingFieldError(e) => RecutError::MissingFieldError(MissingFieldError {
+                line: Some(line),
+                ..e
+            }),
+            RecutError::LineRangeError(e) => RecutError::LineRangeError(LineRangeError {
+                line: Some(line),
+                ..e
+            }),
+            e => e,
         }
     }
 }
@@ -31,9 +71,16 @@ impl error::Error for RecutError {
         match *self {
             RecutError::IntError(ref e) => Some(e),
             RecutError::InputError(ref e) => Some(e),
+            RecutError::OutputError(ref e) => Some(e),
             RecutError::InputRangeParseError(ref e) => Some(e),
             RecutError::RegexError(ref e) => Some(e),
             RecutError::RangeValueError(ref e) => Some(e),
+            RecutError::MissingFieldError(ref e) => Some(e),
+            RecutError::LineRangeError(ref e) => Some(e),
+            RecutError::NoInputError => None,
+            RecutError::NotBytesError => None,
+            RecutError::DelimiterInferenceError(ref e) => Some(e),
+            RecutError::UnclosedQuoteError(ref e) => Some(e),
         }
     }
 }
@@ -57,6 +104,26 @@ impl From<regex::Error> for RecutError {
         RecutError::RegexError(err)
     }
 }
+impl From<MissingFieldError> for RecutError {
+    fn from(err: MissingFieldError) -> Self {
+        RecutError::MissingFieldError(err)
+    }
+}
+impl From<LineRangeError> for RecutError {
+    fn from(err: LineRangeError) -> Self {
+        RecutError::LineRangeError(err)
+    }
+}
+impl From<DelimiterInferenceError> for RecutError {
+    fn from(err: DelimiterInferenceError) -> Self {
+        RecutError::DelimiterInferenceError(err)
+    }
+}
+impl From<UnclosedQuoteError> for RecutError {
+    fn from(err: UnclosedQuoteError) -> Self {
+        RecutError::UnclosedQuoteError(err)
+    }
+}
 #[derive(Debug)]
 pub struct RangeError {}
 
@@ -70,3 +137,156 @@ impl error::Error for RangeError {
         None
     }
 }
+
+/**
+A line did not have an item at an index the list asked for,
+the index is the position it resolved to so it is negative when before the start of the line
+*/
+#[derive(Debug)]
+pub struct MissingFieldError {
+    pub line: Option<usize>,
+    pub index: i64,
+    pub length: usize,
+}
+
+impl MissingFieldError {
+    pub fn new(index: i64, length: usize) -> MissingFieldError {
+        MissingFieldError {
+            line: None,
+            index,
+            length,
+        }
+    }
+}
+
+impl Display for MissingFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "Line {}: ", line)?;
+        }
+        write!(
+            f,
+            "index {} is missing, the line only has {} items. Use --missing to skip or fill short lines",
+            self.index, self.length
+        )
+    }
+}
+impl error::Error for MissingFieldError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/**
+A range mixing positive and negative ends had its start after its end for the length of a line
+*/
+#[derive(Debug)]
+pub struct LineRangeError {
+    pub line: Option<usize>,
+    pub begin: i32,
+    pub end: i32,
+    pub length: usize,
+}
+
+impl LineRangeError {
+    pub fn new(begin: i32, end: i32, length: usize) -> LineRangeError {
+        LineRangeError {
+            line: None,
+            begin,
+            end,
+            length,
+        }
+    }
+}
+
+impl Display for LineRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "Line {}: ", line)?;
+        }
+        let resolve = |num: i32| {
+            if num >= 0 {
+                num as i64
+            } else {
+                self.length as i64 + num as i64
+            }
+        };
+        write!(
+            f,
+            "range {}:{} resolves to {}:{} for a line of {} items, the start is after the end. Use --missing to skip or fill such lines",
+            self.begin,
+            self.end,
+            resolve(self.begin),
+            resolve(self.end),
+            self.length
+        )
+    }
+}
+impl error::Error for LineRangeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/**
+None of the sampled lines had any punctuation or whitespace between data to use as a delimiter
+*/
+#[derive(Debug)]
+pub struct DelimiterInferenceError {
+    pub sampled_lines: usize,
+}
+
+impl Display for DelimiterInferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sampled_lines == 0 {
+            write!(
+                f,
+                "Could not infer a delimiter, the sampled lines are blank"
+            )?;
+        } else {
+            write!(
+                f,
+                "Could not infer a delimiter, none of the {} sampled lines have punctuation or whitespace between data",
+                self.sampled_lines
+            )?;
+        }
+        write!(
+            f,
+            ". Give the delimiter with -d for a regex or -s for a string"
+        )
+    }
+}
+impl error::Error for DelimiterInferenceError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/**
+A quoted field was still open when the input ended, the line is where its record started
+*/
+#[derive(Debug)]
+pub struct UnclosedQuoteError {
+    pub line: usize,
+}
+
+impl UnclosedQuoteError {
+    pub fn new(line: usize) -> UnclosedQuoteError {
+        UnclosedQuoteError { line }
+    }
+}
+
+impl Display for UnclosedQuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Line {}: a quoted field is not closed by the end of the input. Give the quote with --quote if it is not the one inferred",
+            self.line
+        )
+    }
+}
+impl error::Error for UnclosedQuoteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
@@ -0,0 +1,91 @@
+use crate::infer::DelimiterCandidate;
+use std::{fmt, ops::RangeInclusive};
+
+/**
+Each pattern of a match cut type with the index and name of every header field it matched
+*/
+pub type HeaderMatches = Vec<(String, Vec<(usize, String)>)>;
+
+/**
+The plan a cut resolved to from the first line of its input, shown by `--explain`
+*/
+#[derive(Debug)]
+pub struct Explanation {
+    pub cut_type: String,
+    pub delimiter: Option<String>,
+    pub delimiter_reason: Option<String>,
+    pub delimiter_ranking: Vec<DelimiterCandidate>,
+    pub quoting: Option<String>,
+    pub output_delimiter: String,
+    pub indices: Vec<String>,
+    pub matches: HeaderMatches,
+    pub first_line_length: usize,
+    /// The positions each list entry covers in the first line
+    pub first_line_indices: Result<Vec<RangeInclusive<i64>>, String>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cut type: {}", self.cut_type)?;
+
+        if let Some(delimiter) = &self.delimiter {
+            writeln!(f, "Delimiter: {}", delimiter)?;
+        }
+        if let Some(reason) = &self.delimiter_reason {
+            writeln!(f, "  {}", reason)?;
+        }
+        if !self.delimiter_ranking.is_empty() {
+            writeln!(f, "Delimiter candidates:")?;
+            for candidate in &self.delimiter_ranking {
+                writeln!(
+                    f,
+                    "  {:?} {} times on {} lines",
+                    candidate.delimiter, candidate.count, candidate.lines
+                )?;
+            }
+        }
+        if let Some(quoting) = &self.quoting {
+            writeln!(f, "Quote: {}", quoting)?;
+        }
+        writeln!(f, "Output delimiter: {:?}", self.output_delimiter)?;
+
+        if !self.indices.is_empty() {
+            writeln!(f, "Parsed list:")?;
+            for index in &self.indices {
+                writeln!(f, "  {}", index)?;
+            }
+        }
+
+        if !self.matches.is_empty() {
+            writeln!(f, "Header matches:")?;
+            for (pattern, matched) in &self.matches {
+                let matched = matched
+                    .iter()
+                    .map(|(i, name)| format!("{} {:?}", i, name))
+                    .collect::<Vec<_>>();
+                if matched.is_empty() {
+                    writeln!(f, "  {:?} matched nothing", pattern)?;
+                } else {
+                    writeln!(f, "  {:?} matched {}", pattern, matched.join(", "))?;
+                }
+            }
+        }
+
+        writeln!(f, "First line has {} items", self.first_line_length)?;
+        match &self.first_line_indices {
+            Ok(indices) => {
+                let indices = indices
+                    .iter()
+                    .filter(|range| !range.is_empty())
+                    .map(|range| match range.end() - range.start() {
+                        0 => range.start().to_string(),
+                        1 => format!("{},{}", range.start(), range.end()),
+                        _ => format!("{}..{}", range.start(), range.end()),
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(f, "First line resolves to indices: {}", indices.join(","))
+            }
+            Err(e) => writeln!(f, "First line does not resolve: {}", e),
+        }
+    }
+}
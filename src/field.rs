@@ -1,72 +1,471 @@
-use pest::Parser;
+use crate::error::{RecutError, UnclosedQuoteError};
+use crate::TrimPolicy;
 use regex::Regex;
-#[derive(Parser)]
-#[grammar = "line.pest"]
-pub struct LineParser;
+use std::{borrow::Cow, io, iter::Peekable, str::CharIndices};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-enum LastRule {
-    Data,
-    QuotedData,
-    None,
+/**
+The character fields are quoted with and the escape for it inside a quoted field.
+When the escape is the quote itself a quote is escaped by doubling it as in CSV,
+otherwise the escape makes whatever character follows it part of the field
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quoting {
+    pub quote: char,
+    pub escape: char,
 }
 
-enum DelimiterType<'a> {
-    String(&'a str),
-    Regex(&'a Regex),
+impl Quoting {
+    pub fn new(quote: char, escape: Option<char>) -> Quoting {
+        Quoting {
+            quote,
+            escape: escape.unwrap_or(quote),
+        }
+    }
+
+    /**
+    Moves past a quoted field up to and including its closing quote,
+    returning false when the text ends first and the field is left open
+    */
+    fn close(&self, chars: &mut Peekable<CharIndices>) -> bool {
+        while let Some((_, c)) = chars.next() {
+            if c == self.escape && self.escape != self.quote {
+                chars.next();
+            } else if c == self.quote {
+                if self.escape == self.quote && chars.peek().map(|(_, c)| *c) == Some(self.quote) {
+                    chars.next(); //doubled
+                } else {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /**
+    Breaks a line into quoted fields, including their quotes, and the data between them, along with
+    whether a quoted field is left open at the end. `open` is whether the line starts inside a quoted
+    field carried over from the line before. A quote only opens a field at the start of one, after
+    a delimiter or the start of the line and any whitespace the trim policy trims from the field
+    */
+    fn scan<'l>(
+        &self,
+        line: &'l str,
+        delimiter: &DelimiterType,
+        trim: &TrimPolicy,
+        open: bool,
+    ) -> (Vec<Segment<'l>>, bool) {
+        let mut segments = vec![];
+        let mut chars = line.char_indices().peekable();
+        let mut open = open;
+        let mut quoted_start = 0;
+        let mut data_start = 0;
+
+        loop {
+            if open {
+                if !self.close(&mut chars) {
+                    segments.push(Segment::Quoted(&line[quoted_start..]));
+                    return (segments, true);
+                }
+                let end = chars.peek().map_or(line.len(), |(end, _)| *end);
+                segments.push(Segment::Quoted(&line[quoted_start..end]));
+                data_start = end;
+                open = false;
+            }
+            let (start, c) = match chars.next() {
+                Some(next) => next,
+                None => break,
+            };
+            let before = &line[data_start..start];
+            if c == self.quote && starts_field(before, data_start == 0, delimiter, trim) {
+                if start > data_start {
+                    segments.push(Segment::Data(before));
+                }
+                quoted_start = start;
+                open = true;
+            }
+        }
+        if data_start < line.len() {
+            segments.push(Segment::Data(&line[data_start..]));
+        }
+
+        (segments, false)
+    }
+
+    /**
+    Breaks a line into quoted fields, including their quotes, and the data between them.
+    A quoted field left open runs to the end of the line
+    */
+    pub fn segments<'l>(
+        &self,
+        line: &'l str,
+        delimiter: &DelimiterType,
+        trim: &TrimPolicy,
+    ) -> Vec<Segment<'l>> {
+        self.scan(line, delimiter, trim, false).0
+    }
+
+    /**
+    Whether a quoted field is open at the end of a line, given whether one was open at its start
+    */
+    fn open_after(
+        &self,
+        line: &str,
+        delimiter: &DelimiterType,
+        trim: &TrimPolicy,
+        open: bool,
+    ) -> bool {
+        self.scan(line, delimiter, trim, open).1
+    }
 }
 
-impl<'a> DelimiterType<'a> {
-    fn split(&'a self, line: &'a str) -> Vec<&'a str> {
+/**
+Whether a quote following `before`, the text since the start of the line or the end of the last
+quoted field, is at the start of a field
+*/
+fn starts_field(
+    before: &str,
+    line_start: bool,
+    delimiter: &DelimiterType,
+    trim: &TrimPolicy,
+) -> bool {
+    let trimmed = match trim {
+        TrimPolicy::Both | TrimPolicy::Left => before.trim_end(),
+        TrimPolicy::None | TrimPolicy::Right => before,
+    };
+    [before, trimmed]
+        .iter()
+        .any(|text| (line_start && text.is_empty()) || delimiter.ends(text))
+}
+
+impl Default for Quoting {
+    fn default() -> Self {
+        Quoting::new('"', None)
+    }
+}
+
+/**
+Part of a line, either a quoted field with its quotes or data that is not quoted
+*/
+pub enum Segment<'l> {
+    Data(&'l str),
+    Quoted(&'l str),
+}
+
+/**
+What fields are separated by, which decides where a quoted field can start.
+Until the delimiter is known any punctuation or whitespace could be it
+*/
+#[derive(Clone, Debug)]
+pub enum DelimiterType<'a> {
+    String(Cow<'a, str>),
+    Regex(Cow<'a, Regex>),
+    Unknown,
+}
+
+impl DelimiterType<'_> {
+    fn split<'l>(&self, line: &'l str) -> Vec<&'l str> {
         match self {
-            DelimiterType::String(delimiter) => line.split(delimiter).collect(),
+            DelimiterType::String(delimiter) => line.split(delimiter.as_ref()).collect(),
             DelimiterType::Regex(regex_delim) => regex_delim.split(line).collect(),
+            DelimiterType::Unknown => vec![line],
+        }
+    }
+
+    /**
+    Whether text ends with the delimiter, so that whatever follows it starts a field
+    */
+    fn ends(&self, text: &str) -> bool {
+        match self {
+            DelimiterType::String(delimiter) => text.ends_with(delimiter.as_ref()),
+            DelimiterType::Regex(regex_delim) => regex_delim
+                .find_iter(text)
+                .any(|found| found.end() == text.len() && !found.as_str().is_empty()),
+            DelimiterType::Unknown => text
+                .chars()
+                .last()
+                .is_some_and(|c| c.is_whitespace() || c.is_ascii_punctuation()),
         }
     }
 }
 
-pub fn split_line_quotes(line: &str, delimiter: &str) -> Vec<String> {
-    split_qoutes(line, DelimiterType::String(delimiter))
-}
-pub fn split_line_regex_quotes(line: &str, regex_delim: &Regex) -> Vec<String> {
-    split_qoutes(line, DelimiterType::Regex(regex_delim))
-}
-
-fn split_qoutes(line: &str, splitter: DelimiterType) -> Vec<String> {
-    LineParser::parse(Rule::line, line)
-        .unwrap()
-        .into_iter()
-        .next() //one matching the first line at a time
-        .unwrap()
-        .into_inner()
-        .into_iter()
-        .fold(
-            (vec![], LastRule::None), //lastrule for state and vec for output
-            |(mut split_line, last_rule), inner| match inner.as_rule() {
-                Rule::data => {
-                    let split_data = splitter.split(inner.as_str()).into_iter();
-
-                    let mut result = if last_rule != LastRule::None {
-                        split_data
-                            .skip(1) // first field would have been in quoted data
-                            .map(|x| x.trim().to_owned())
-                            .collect::<Vec<_>>()
-                    } else {
-                        split_data.map(|x| x.trim().to_owned()).collect::<Vec<_>>()
-                    };
-                    split_line.append(&mut result);
-                    (split_line, LastRule::Data)
+/**
+Trims whitespace from either or both ends of a field
+*/
+pub fn trim_field<'f>(field: &'f str, trim: &TrimPolicy) -> &'f str {
+    match trim {
+        TrimPolicy::None => field,
+        TrimPolicy::Both => field.trim(),
+        TrimPolicy::Left => field.trim_start(),
+        TrimPolicy::Right => field.trim_end(),
+    }
+}
+
+pub fn split_line_quotes<'l>(
+    line: &'l str,
+    delimiter: &str,
+    quoting: &Quoting,
+    trim: &TrimPolicy,
+) -> Vec<&'l str> {
+    split_qoutes(
+        line,
+        DelimiterType::String(Cow::Borrowed(delimiter)),
+        quoting,
+        trim,
+    )
+}
+pub fn split_line_regex_quotes<'l>(
+    line: &'l str,
+    regex_delim: &Regex,
+    quoting: &Quoting,
+    trim: &TrimPolicy,
+) -> Vec<&'l str> {
+    split_qoutes(
+        line,
+        DelimiterType::Regex(Cow::Borrowed(regex_delim)),
+        quoting,
+        trim,
+    )
+}
+
+fn split_qoutes<'l>(
+    line: &'l str,
+    splitter: DelimiterType,
+    quoting: &Quoting,
+    trim: &TrimPolicy,
+) -> Vec<&'l str> {
+    let mut split_line = vec![];
+    let mut after_quoted = None; //whether the last segment was quoted, none at the start
+
+    for segment in quoting.segments(line, &splitter, trim) {
+        match segment {
+            Segment::Data(data) => {
+                let split_data = splitter.split(data).into_iter();
+
+                let mut result = if after_quoted.is_some() {
+                    split_data
+                        .skip(1) // first field would have been in quoted data
+                        .map(|x| trim_field(x, trim))
+                        .collect::<Vec<_>>()
+                } else {
+                    split_data.map(|x| trim_field(x, trim)).collect::<Vec<_>>()
+                };
+                split_line.append(&mut result);
+                after_quoted = Some(false);
+            }
+            Segment::Quoted(quoted) => {
+                if after_quoted == Some(false) {
+                    // remove empty field from the data before as the quoted data is its actual contents
+                    split_line.pop();
                 }
-                Rule::quoted_data => {
-                    if last_rule == LastRule::Data {
-                        split_line = split_line.split_last().unwrap().1.to_owned();
-                        // remove empty filed from last iteration as current field is its actua contents
-                    }
-                    split_line.push(inner.as_str().to_owned());
-                    (split_line, LastRule::QuotedData)
+                split_line.push(quoted);
+                after_quoted = Some(true);
+            }
+        }
+    }
+
+    split_line
+}
+
+/**
+Strips the quotes around a quoted field and unescapes the quotes inside it,
+fields that are not quoted are returned as they are
+*/
+pub fn unquote<'f>(field: &'f str, quoting: &Quoting) -> Cow<'f, str> {
+    let inner = match field
+        .strip_prefix(quoting.quote)
+        .and_then(|field| field.strip_suffix(quoting.quote))
+    {
+        Some(inner) => inner,
+        None => return Cow::Borrowed(field),
+    };
+
+    if quoting.escape == quoting.quote {
+        let doubled = quoting.quote.to_string().repeat(2);
+        if inner.contains(&doubled) {
+            return Cow::Owned(inner.replace(&doubled, &quoting.quote.to_string()));
+        }
+    } else if inner.contains(quoting.escape) {
+        let mut unescaped = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c == quoting.escape => unescaped.extend(chars.next()),
+                c => unescaped.push(c),
+            }
+        }
+        return Cow::Owned(unescaped);
+    }
+    Cow::Borrowed(inner)
+}
+
+/**
+Reads the records of an input along with the line each starts on. When quotes are recognised
+a record carries on over the next line while a quoted field is still open, as RFC 4180 allows,
+the line breaks inside the field are kept as `\n`. A quoted field still open at the end of
+the input is an error
+*/
+pub struct RecordLines<I: Iterator<Item = io::Result<String>>> {
+    lines: I,
+    quoting: Option<Quoting>,
+    delimiter: DelimiterType<'static>,
+    trim: TrimPolicy,
+    line_number: usize,
+}
+
+impl<I: Iterator<Item = io::Result<String>>> RecordLines<I> {
+    pub fn new(
+        lines: I,
+        quoting: Option<Quoting>,
+        delimiter: DelimiterType<'static>,
+        trim: TrimPolicy,
+    ) -> RecordLines<I> {
+        RecordLines {
+            lines,
+            quoting,
+            delimiter,
+            trim,
+            line_number: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = io::Result<String>>> Iterator for RecordLines<I> {
+    type Item = Result<(usize, String), RecutError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        self.line_number += 1;
+        let start = self.line_number;
+
+        let quoting = match &self.quoting {
+            Some(quoting) => quoting,
+            None => return Some(Ok((start, record))),
+        };
+        let mut open = quoting.open_after(&record, &self.delimiter, &self.trim, false);
+        while open {
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    self.line_number += 1;
+                    open = quoting.open_after(&line, &self.delimiter, &self.trim, true);
+                    record.push('\n');
+                    record.push_str(&line);
                 }
-                _ => unreachable!(),
-            },
-        )
-        .0
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => return Some(Err(UnclosedQuoteError::new(start).into())),
+            }
+        }
+
+        Some(Ok((start, record)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::MissingFieldError;
+    use crate::{CutOptions, CutType, Cutter, MissingPolicy, RangeDelimiter};
+
+    fn record_lines(input: &str) -> Vec<Result<(usize, String), RecutError>> {
+        let lines = input.lines().map(|line| Ok(line.to_owned()));
+        let delimiter = DelimiterType::String(Cow::Borrowed(","));
+        RecordLines::new(lines, Some(Quoting::default()), delimiter, TrimPolicy::None).collect()
+    }
+
+    fn cut_records(
+        cut_type: CutType,
+        options: &CutOptions,
+        input: &str,
+    ) -> Result<Vec<Vec<String>>, RecutError> {
+        let options = CutOptions {
+            quote: Some('"'),
+            ..options.clone()
+        };
+        Cutter::new(cut_type, &options)?
+            .records(io::Cursor::new(input))?
+            .map(|record| Ok(record?.into_iter().map(Cow::into_owned).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn quoted_field_spans_lines() {
+        let records = record_lines("a,\"b\nc\",d\ne,f\n")
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            [(1, String::from("a,\"b\nc\",d")), (3, String::from("e,f"))]
+        );
+    }
+
+    #[test]
+    fn fields_across_lines() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("1", ","));
+        let records = cut_records(cut_type, &CutOptions::default(), "a,\"b\nc\",d\ne,f\n").unwrap();
+        assert_eq!(records, [["b\nc"], ["f"]]);
+    }
+
+    #[test]
+    fn matches_across_lines() {
+        let cut_type = CutType::MatchesStringDelimiter(RangeDelimiter::new("note", ","));
+        let input = "name,note\nx,\"two\nlines\"\ny,z\n";
+        let records = cut_records(cut_type, &CutOptions::default(), input).unwrap();
+        assert_eq!(records, [["note"], ["two\nlines"], ["z"]]);
+    }
+
+    #[test]
+    fn quote_inside_field() {
+        let records = record_lines("5\",6\n7,8\n")
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            [(1, String::from("5\",6")), (2, String::from("7,8"))]
+        );
+
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("0", ","));
+        let records = cut_records(cut_type, &CutOptions::default(), "5\",6\n7,8\n").unwrap();
+        assert_eq!(records, [["5\""], ["7"]]);
+    }
+
+    #[test]
+    fn unclosed_quote_reports_start_line() {
+        let records = record_lines("a,b\nc,\"d\ne,f\n");
+        assert!(matches!(records[0], Ok((1, _))));
+        assert!(matches!(
+            records[1],
+            Err(RecutError::UnclosedQuoteError(UnclosedQuoteError {
+                line: 2
+            }))
+        ));
+    }
+
+    #[test]
+    fn record_crosses_sample_boundary() {
+        let options = CutOptions {
+            sample_lines: 2,
+            ..CutOptions::default()
+        };
+        let input = "a,b\nc,\"d\ne\"\nf,g\n";
+        let records = cut_records(CutType::FieldsInferDelimiter("1"), &options, input).unwrap();
+        assert_eq!(records, [["b"], ["d\ne"], ["g"]]);
+    }
+
+    #[test]
+    fn line_numbers_after_multiline_record() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("1", ","));
+        let options = CutOptions {
+            missing: MissingPolicy::Error,
+            ..CutOptions::default()
+        };
+        let error = cut_records(cut_type, &options, "a,\"b\nc\"\nd,e\nf\n").unwrap_err();
+        assert!(matches!(
+            error,
+            RecutError::MissingFieldError(MissingFieldError { line: Some(4), .. })
+        ));
+    }
 }
@@ -0,0 +1,106 @@
+use crate::error::RecutError;
+use crate::field::trim_field;
+use crate::{TrimPolicy, WidthUnit};
+use std::iter;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/**
+Fields at fixed offsets of a line, as in aligned tables and reports without a delimiter.
+Each field has a start and, unless it runs to the end of the line, an end
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedWidths {
+    spans: Vec<(usize, Option<usize>)>,
+    unit: WidthUnit,
+}
+
+impl FixedWidths {
+    /**
+    Fields starting at each character offset and ending where the next one starts,
+    the last runs to the end of the line
+    */
+    pub fn from_starts(starts: &[usize]) -> FixedWidths {
+        let ends = starts
+            .iter()
+            .skip(1)
+            .map(|&end| Some(end))
+            .chain(iter::once(None));
+        FixedWidths {
+            spans: starts.iter().copied().zip(ends).collect(),
+            unit: WidthUnit::Characters,
+        }
+    }
+
+    /**
+    Parses a width specification such as `8,12,5,*`, the width of each field in turn.
+    A final `*` is a field running to the end of the line, without it the rest of the line is dropped
+    */
+    pub fn parse(spec: &str, unit: WidthUnit) -> Result<FixedWidths, RecutError> {
+        let mut widths = spec.split(',').map(str::trim).collect::<Vec<_>>();
+        let rest = widths.last() == Some(&"*");
+        if rest {
+            widths.pop();
+        }
+
+        let mut spans = Vec::with_capacity(widths.len() + 1);
+        let mut start = 0;
+        for width in widths {
+            let end = start + width.parse::<usize>()?;
+            spans.push((start, Some(end)));
+            start = end;
+        }
+        if rest {
+            spans.push((start, None));
+        }
+
+        Ok(FixedWidths { spans, unit })
+    }
+
+    /**
+    Where each field starts and ends, written as a list such as `0:8,8:20,20:`
+    */
+    pub fn describe(&self) -> String {
+        let spans = self
+            .spans
+            .iter()
+            .map(|(start, end)| match end {
+                Some(end) => format!("{}:{}", start, end),
+                None => format!("{}:", start),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        match self.unit {
+            WidthUnit::Characters => format!("{} characters", spans),
+            WidthUnit::Columns => format!("{} display columns", spans),
+        }
+    }
+
+    /**
+    Slices a line into its fields with the padding around each trimmed by the trim policy.
+    Every line has every field, so a line ending early has empty fields at the end.
+    Counting display columns a wide character crossing the end of a field belongs to the next
+    */
+    pub fn split<'l>(&self, line: &'l str, trim: &TrimPolicy) -> Vec<&'l str> {
+        // the byte offset each position starts at
+        let offsets = match self.unit {
+            WidthUnit::Characters => line
+                .char_indices()
+                .map(|(offset, _)| offset)
+                .collect::<Vec<_>>(),
+            WidthUnit::Columns => line
+                .grapheme_indices(true)
+                .flat_map(|(offset, grapheme)| iter::repeat_n(offset, grapheme.width().min(2)))
+                .collect::<Vec<_>>(),
+        };
+        let offset = |position: usize| offsets.get(position).copied().unwrap_or(line.len());
+
+        self.spans
+            .iter()
+            .map(|(start, end)| {
+                let end = end.map_or(line.len(), offset);
+                trim_field(&line[offset(*start)..end], trim)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,351 @@
+use crate::error::DelimiterInferenceError;
+use crate::field::{DelimiterType, Quoting, Segment};
+use crate::fixed::FixedWidths;
+use crate::TrimPolicy;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/**
+A delimiter inferred from a sample of lines along with why it was chosen
+*/
+pub struct Inference {
+    pub delimiter: String,
+    pub regex: bool,
+    pub reason: String,
+    pub ranking: Vec<DelimiterCandidate>,
+}
+
+/**
+The regex inferred for column aligned input, such as the output of `ps` or `df`
+*/
+pub const WHITESPACE_RUN: &str = r"\s+";
+
+/**
+Finds each run of punctuation or whitespace between data in a line, quoted fields are skipped
+*/
+fn delimiter_runs<'l>(line: &'l str, quoting: &Quoting, delimiter_chars: &Regex) -> Vec<&'l str> {
+    quoting
+        .segments(line, &DelimiterType::Unknown, &TrimPolicy::default())
+        .into_iter()
+        .flat_map(|segment| match segment {
+            Segment::Data(data) => delimiter_chars
+                .find_iter(data)
+                .map(|found| found.as_str())
+                .collect(),
+            Segment::Quoted(_) => vec![],
+        })
+        .collect()
+}
+
+/**
+Splits a run into the shortest text it repeats, so `,,` from an empty field counts as two commas
+and `, , ` as two of `, `
+*/
+fn repeated_unit(run: &str) -> (&str, usize) {
+    let length = run.len();
+    (1..=length)
+        .filter(|unit| run.is_char_boundary(*unit))
+        .map(|unit| (&run[..unit], length / unit))
+        .find(|(unit, times)| unit.repeat(*times) == run)
+        .unwrap_or((run, 1))
+}
+
+/**
+Whether a run is whitespace that could line up columns. Tabs alone are left out, `\t\t` is an empty
+field between tab delimiters
+*/
+fn is_alignment(run: &str) -> bool {
+    run.chars().all(char::is_whitespace) && run.chars().any(|c| c != '\t')
+}
+
+/**
+Counts how many times each candidate delimiter appears between data in a line.
+When `aligned` every run of whitespace apart from tabs counts once as the candidate `WHITESPACE_RUN`,
+a single space also counting as a space so that the two are ranked against each other
+*/
+fn count_candidates<'l>(runs: &[&'l str], aligned: bool) -> BTreeMap<&'l str, usize> {
+    let mut potential_delimiters = BTreeMap::new();
+    for run in runs {
+        let alignment = aligned && is_alignment(run);
+        if alignment {
+            *potential_delimiters.entry(WHITESPACE_RUN).or_insert(0) += 1;
+        }
+        if !alignment || run.chars().count() == 1 {
+            let (delimiter, times) = repeated_unit(run);
+            *potential_delimiters.entry(delimiter).or_insert(0) += times;
+        }
+    }
+    potential_delimiters
+}
+
+/**
+A possible delimiter and how regularly it appears across the sampled lines. `count` is the number
+of times it most often appears on a line and `lines` is how many sampled lines have exactly that many
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelimiterCandidate {
+    pub delimiter: String,
+    /// The delimiter is the regex `WHITESPACE_RUN` rather than a string
+    pub regex: bool,
+    pub count: usize,
+    pub lines: usize,
+}
+
+const OTHER: usize = 5;
+
+/**
+Order of the usual delimiters, tab then comma, semicolon, pipe and space before any other punctuation.
+A delimiter padded with whitespace such as `, ` takes the order of the delimiter
+*/
+fn precedence(delimiter: &str) -> usize {
+    let trimmed = delimiter.trim();
+    let delimiter = if trimmed.is_empty() {
+        delimiter
+    } else {
+        trimmed
+    };
+    match delimiter {
+        "\t" => 0,
+        "," => 1,
+        ";" => 2,
+        "|" => 3,
+        " " | WHITESPACE_RUN => 4,
+        _ => OTHER,
+    }
+}
+
+/**
+Ranks every candidate delimiter in the sample, best first. Repeated runs such as `, ` or ` | `
+are candidates of their own, and when columns are aligned with several spaces every run of
+whitespace is also the regex candidate `WHITESPACE_RUN`. Candidates appearing the same number of
+times on more non empty lines rank higher. Among those tab, comma, semicolon, pipe and space,
+alone or padded with whitespace, rank ahead of other punctuation such as `-` or `.` found inside
+dates and addresses, then candidates appearing more often on each line rank higher with ties
+settled by the precedence tab > comma > semicolon > pipe > space
+*/
+pub fn rank_delimiters(sample: &[String], quoting: &Quoting) -> Vec<DelimiterCandidate> {
+    let delimiter_chars = Regex::new(r"[\p{White_Space}\p{Punctuation}|]+").unwrap(); //harcoded should succeed
+
+    // indentation and trailing padding are not delimiters
+    let runs = sample
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| delimiter_runs(line, quoting, &delimiter_chars))
+        .collect::<Vec<_>>();
+    // whitespace longer than a single character only lines up columns
+    let aligned = runs
+        .iter()
+        .flatten()
+        .any(|run| is_alignment(run) && run.chars().count() > 1);
+    let counts = runs
+        .iter()
+        .map(|runs| count_candidates(runs, aligned))
+        .collect::<Vec<_>>();
+
+    let mut per_line_counts = BTreeMap::new();
+    for line in &counts {
+        for (delimiter, count) in line {
+            per_line_counts
+                .entry(*delimiter)
+                .or_insert_with(BTreeMap::new)
+                .entry(*count)
+                .and_modify(|lines| *lines += 1)
+                .or_insert(1);
+        }
+    }
+
+    let mut ranking = per_line_counts
+        .iter()
+        .map(|(delimiter, line_counts)| {
+            // the most common count, the highest count wins when lines are split evenly
+            let (count, lines) = line_counts
+                .iter()
+                .max_by_key(|(count, lines)| (**lines, **count))
+                .unwrap();
+            DelimiterCandidate {
+                delimiter: delimiter.to_string(),
+                regex: *delimiter == WHITESPACE_RUN,
+                count: *count,
+                lines: *lines,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let usual = |candidate: &DelimiterCandidate| precedence(&candidate.delimiter) < OTHER;
+    ranking.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then(usual(b).cmp(&usual(a)))
+            .then(b.count.cmp(&a.count))
+            .then(precedence(&a.delimiter).cmp(&precedence(&b.delimiter)))
+            .then(a.delimiter.cmp(&b.delimiter))
+    });
+    ranking
+}
+
+/**
+Infers the delimiter from the first lines of the input the way CSV sniffers do, picking the best
+ranked candidate so a title line above the data does not decide the delimiter
+*/
+pub fn infer_delimiter(
+    sample: &[String],
+    quoting: &Quoting,
+) -> Result<Inference, DelimiterInferenceError> {
+    let sampled_lines = sample.iter().filter(|line| !line.trim().is_empty()).count();
+    let ranking = rank_delimiters(sample, quoting);
+    let best = ranking
+        .first()
+        .ok_or(DelimiterInferenceError { sampled_lines })?;
+
+    let reason = format!(
+        "appears {} times on {} of {} sampled lines",
+        best.count, best.lines, sampled_lines
+    );
+
+    Ok(Inference {
+        delimiter: best.delimiter.to_owned(),
+        regex: best.regex,
+        reason,
+        ranking,
+    })
+}
+
+/**
+Whether a quoted field opens and closes somewhere in a line, followed by nothing but its delimiter
+*/
+fn wraps_field(line: &str, quoting: &Quoting) -> bool {
+    let segments = quoting.segments(line, &DelimiterType::Unknown, &TrimPolicy::default());
+    segments
+        .iter()
+        .enumerate()
+        .any(|(i, segment)| match segment {
+            Segment::Quoted(quoted) => {
+                let closed = quoted.len() > 1 && quoted.ends_with(quoting.quote);
+                let ends_field = match segments.get(i + 1) {
+                    Some(Segment::Data(after)) => after
+                        .chars()
+                        .next()
+                        .is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation()),
+                    _ => true,
+                };
+                closed && ends_field
+            }
+            Segment::Data(_) => false,
+        })
+}
+
+/**
+Infers whether fields are quoted with `"` or `'` from how many sampled lines have a whole field
+wrapped in each. Single quotes only win clearly, on more lines than double quotes and at least two,
+so that an apostrophe in a word or a lone quoted value does not make them the quote
+*/
+pub fn infer_quote(sample: &[String]) -> char {
+    let quoted_lines = |quote: char| {
+        let quoting = Quoting::new(quote, None);
+        sample
+            .iter()
+            .filter(|line| wraps_field(line, &quoting))
+            .count()
+    };
+
+    let single = quoted_lines('\'');
+    if single >= 2 && single > quoted_lines('"') {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/**
+Infers fixed width columns from whitespace lined up across the sampled lines, for aligned tables
+such as `kubectl get` or `docker ps` where fields can contain single spaces. A column starts
+wherever text follows a position that is blank on every sampled line
+*/
+pub fn infer_columns(sample: &[String]) -> Result<FixedWidths, DelimiterInferenceError> {
+    let lines = sample
+        .iter()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().map(char::is_whitespace).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+    let blank = (0..width)
+        .map(|i| {
+            lines
+                .iter()
+                .all(|line| line.get(i).is_none_or(|&blank| blank))
+        })
+        .collect::<Vec<_>>();
+    let mut starts = (0..width)
+        .filter(|&i| !blank[i] && (i == 0 || blank[i - 1]))
+        .collect::<Vec<_>>();
+
+    match starts.first_mut() {
+        // indentation belongs to the first column
+        Some(first) => *first = 0,
+        None => return Err(DelimiterInferenceError { sampled_lines: 0 }),
+    }
+    Ok(FixedWidths::from_starts(&starts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(sample: &str) -> Vec<String> {
+        let sample = sample.lines().map(String::from).collect::<Vec<_>>();
+        rank_delimiters(&sample, &Quoting::default())
+            .into_iter()
+            .map(|candidate| candidate.delimiter)
+            .collect()
+    }
+
+    #[test]
+    fn tsv_with_empty_fields() {
+        let ranking = rank("id\tname\tnote\n1\tJohn Smith\t\n2\t\tx\n");
+        assert_eq!(ranking[0], "\t");
+        assert!(!ranking.contains(&String::from(WHITESPACE_RUN)));
+    }
+
+    #[test]
+    fn column_aligned() {
+        let ranking = rank(
+            "  PID TTY          TIME CMD\n 1234 pts/0    00:00:00 bash\n 5678 pts/0    00:00:01 ps\n",
+        );
+        assert_eq!(ranking[0], WHITESPACE_RUN);
+        // single spaces are still ranked, just less consistently
+        assert!(ranking.contains(&String::from(" ")));
+    }
+
+    #[test]
+    fn most_frequent() {
+        // ten commas and one space
+        assert_eq!(rank("a,b,c,d,e,f,g,h,i,j,k l\n")[0], ",");
+    }
+
+    #[test]
+    fn iso_dates() {
+        assert_eq!(rank("1,2020-01-02\n2,2021-03-04\n")[0], ",");
+        assert_eq!(rank("2020-01-02 10:00\n2021-03-04 11:30\n")[0], " ");
+    }
+
+    #[test]
+    fn ip_addresses() {
+        assert_eq!(rank("10.0.0.1,ok\n10.0.0.2,bad\n")[0], ",");
+    }
+
+    #[test]
+    fn padded_delimiter() {
+        assert_eq!(rank("a, 2020-01-02\nb, 2021-03-04\n")[0], ", ");
+    }
+
+    #[test]
+    fn tie_breaking() {
+        assert_eq!(rank("a\tb,c\n")[0], "\t");
+        assert_eq!(rank("a|b;c\n")[0], ";");
+        assert_eq!(rank("a b|c\n")[0], "|");
+        assert_eq!(rank("a-b:c\n")[0], "-");
+    }
+}
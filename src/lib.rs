@@ -1,30 +1,50 @@
 extern crate pest;
 
-use error::RecutError;
-use field::{split_line_quotes, split_line_regex_quotes};
+pub use cutter::{ByteRecords, Cutter, Records};
+pub use error::{DelimiterInferenceError, MissingFieldError, RecutError, UnclosedQuoteError};
+pub use explain::Explanation;
+pub use field::Quoting;
+pub use fixed::FixedWidths;
 use fs::File;
+pub use infer::{infer_columns, infer_quote, rank_delimiters, DelimiterCandidate};
 use io::{stdin, BufRead, BufReader};
-use match_field::{parse_match_indices, parse_match_indices_regex};
-use pest::Parser;
-use range::{parse_indices, BeginRange, EndRange, UnExpandedIndices};
-use regex::Regex;
-use std::{
-    collections::{BTreeMap, HashMap},
-    fs, io,
-};
+pub use sink::{CsvSink, JsonSink, RecordSink, TableSink, WriteSink};
+use std::{fmt, fs, io};
 
 #[macro_use]
 extern crate pest_derive;
 
+mod column;
+mod cutter;
 mod error;
+mod explain;
 mod field;
+mod fixed;
+mod infer;
 mod match_field;
 mod range;
+mod sink;
 
-#[derive(Debug)]
-pub enum IoType {
+pub enum IoType<'a> {
     FromStdIn,
     FromFile(String),
+    FromReader(Box<dyn BufRead + 'a>),
+}
+
+impl<'a> IoType<'a> {
+    pub fn from_reader(reader: impl BufRead + 'a) -> IoType<'a> {
+        IoType::FromReader(Box::new(reader))
+    }
+}
+
+impl fmt::Debug for IoType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoType::FromStdIn => write!(f, "FromStdIn"),
+            IoType::FromFile(file_name) => f.debug_tuple("FromFile").field(file_name).finish(),
+            IoType::FromReader(_) => write!(f, "FromReader"),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -37,12 +57,20 @@ pub struct RangeDelimiter<'a> {
 pub enum CutType<'a> {
     Bytes(&'a str, bool),
     Characters(&'a str),
+    Graphemes(&'a str),
+    Columns(&'a str),
     FieldsInferDelimiter(&'a str),
     FieldsRegexDelimiter(RangeDelimiter<'a>),
     FieldsStringDelimiter(RangeDelimiter<'a>),
     MatchesInferDelimiter(&'a str),
     MatchesRegexDelimiter(RangeDelimiter<'a>),
     MatchesStringDelimiter(RangeDelimiter<'a>),
+    FieldsInferColumns(&'a str),
+    MatchesInferColumns(&'a str),
+    /// The delimiter is a width specification such as `8,12,5,*`
+    FieldsFixedWidth(RangeDelimiter<'a>),
+    /// The delimiter is a width specification such as `8,12,5,*`
+    MatchesFixedWidth(RangeDelimiter<'a>),
 }
 
 impl RangeDelimiter<'_> {
@@ -53,328 +81,375 @@ impl RangeDelimiter<'_> {
         }
     }
 }
-#[derive(Parser)]
-#[grammar = "input.pest"]
-pub struct InputParser;
-
-pub fn cut(input: IoType, cut_type: CutType) -> Result<(), RecutError> {
-    //general handling of input for either the console or a file
-    let input: Box<dyn BufRead> = match input {
-        IoType::FromStdIn => Box::new(BufReader::new(stdin())),
-        IoType::FromFile(file_name) => {
-            let file = File::open(file_name)?;
-
-            let reader = BufReader::new(file);
-
-            Box::new(BufReader::new(reader))
-        }
-    };
-
-    match cut_type {
-        CutType::Bytes(range, split) => {
-            let parsed_indices = parse_indices(range)?;
-            print_by_bytes(input, split, &parsed_indices)?;
-        }
-        CutType::Characters(range) => {
-            let parsed_indices = parse_indices(range)?;
-            print_by_character(input, &parsed_indices)?;
-        }
-        CutType::FieldsInferDelimiter(range) => {
-            let parsed_indices = parse_indices(range)?;
-            print_infer_regex(input, &parsed_indices)?;
-        }
-        CutType::FieldsRegexDelimiter(range) => {
-            let parsed_indices = parse_indices(range.locations)?;
-            print_by_regex(input, &range.delimiter, &parsed_indices)?;
-        }
-        CutType::FieldsStringDelimiter(range) => {
-            let parsed_indices = parse_indices(range.locations)?;
-            print_by_string_delimiter(input, &range.delimiter, &parsed_indices)?;
-        }
-        CutType::MatchesInferDelimiter(range) => {
-            print_match_infer_regex(input, range)?;
-        }
-        CutType::MatchesRegexDelimiter(range) => {
-            print_match_regex_delimiter(input, range.delimiter, range.locations)?
-        }
-        CutType::MatchesStringDelimiter(range) => {
-            print_match_string_delimiter(input, range.delimiter, range.locations)?
-        }
-    }
-
-    Ok(())
+/**
+What to do when a line does not have an item at one of the selected indices
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MissingPolicy {
+    #[default]
+    Error,
+    SkipLine,
+    Empty,
+    Fill(String),
 }
 
-fn print_by_character(
-    input_buffer: Box<dyn BufRead>,
-    input_indices: &[UnExpandedIndices],
-) -> Result<(), RecutError> {
-    for line in input_buffer.lines() {
-        print_line_by_character(&line?, &input_indices);
-    }
-    Ok(())
+/**
+How ranges reaching past either end of a line are treated.
+`Clamp` stops them at the first and last item, `Missing` passes every position outside the line
+to the missing policy. Single indices outside the line always go to the missing policy
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutOfRange {
+    #[default]
+    Clamp,
+    Missing,
 }
 
-fn print_line_by_character(input_line: &str, input_indices: &[UnExpandedIndices]) {
-    let length = input_line.chars().count();
-    let (sorted_indices, expanded_indices) = expand_indices(input_indices, length);
-    let first_index = *sorted_indices.first().unwrap();
-
-    let last_index = *sorted_indices.last().unwrap();
+/**
+What to do with a wide character when only one of the two display columns it covers is selected
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum WidePolicy {
+    Include,
+    Exclude,
+    #[default]
+    Pad,
+}
 
-    let take_length = last_index + 1;
+/**
+Which whitespace is trimmed from the ends of fields
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TrimPolicy {
+    None,
+    #[default]
+    Both,
+    Left,
+    Right,
+}
 
-    let char_map = input_line
-        .char_indices()
-        .skip(first_index)
-        .take(take_length)
-        .collect::<HashMap<_, _>>();
+/**
+What fixed field widths are counted in
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum WidthUnit {
+    #[default]
+    Characters,
+    Columns,
+}
 
-    let mut print_string = String::new();
-    for print_index in &expanded_indices {
-        print_string.push(*char_map.get(print_index).unwrap());
-    }
-    println!("{}", print_string);
+/**
+Settings shared by every cut type
+*/
+#[derive(Clone, Debug)]
+pub struct CutOptions {
+    pub missing: MissingPolicy,
+    pub out_of_range: OutOfRange,
+    pub output_delimiter: Option<String>,
+    pub wide: WidePolicy,
+    /// How many lines from the start of the input are used to infer the delimiter
+    pub sample_lines: usize,
+    pub width_unit: WidthUnit,
+    /// Strip the quotes around quoted fields and collapse doubled quotes inside them, on by default
+    pub unquote: bool,
+    /// The character fields are quoted with, inferred from the sampled lines when not given
+    pub quote: Option<char>,
+    /// The escape for a quote inside a quoted field, without one quotes are doubled as in CSV
+    pub escape: Option<char>,
+    /// Trimming for fields that are not quoted
+    pub trim: TrimPolicy,
+    /// Also trim inside quoted fields once they are unquoted
+    pub trim_quoted: bool,
 }
 
-fn print_by_bytes(
-    input_buffer: Box<dyn BufRead>,
-    splits_allowed: bool,
-    input_indices: &[UnExpandedIndices],
-) -> Result<(), RecutError> {
-    for line in input_buffer.lines() {
-        print_line_by_bytes(&line?, splits_allowed, &input_indices)
+impl Default for CutOptions {
+    fn default() -> Self {
+        CutOptions {
+            missing: MissingPolicy::default(),
+            out_of_range: OutOfRange::default(),
+            output_delimiter: None,
+            wide: WidePolicy::default(),
+            sample_lines: 10,
+            width_unit: WidthUnit::default(),
+            unquote: true,
+            quote: None,
+            escape: None,
+            trim: TrimPolicy::default(),
+            trim_quoted: false,
+        }
     }
-    Ok(())
 }
 
-fn print_line_by_bytes(input_line: &str, splits_alowed: bool, input_indices: &[UnExpandedIndices]) {
-    let length = input_line.bytes().count();
-
-    let (sorted_indices, expanded_indices) = expand_indices(input_indices, length);
+//general handling of input for either the console, a file or a reader supplied by the caller
+fn open_input(input: IoType) -> Result<Box<dyn BufRead + '_>, RecutError> {
+    Ok(match input {
+        IoType::FromStdIn => Box::new(BufReader::new(stdin())),
+        IoType::FromFile(file_name) => {
+            let file = File::open(file_name)?;
 
-    let first_index = *sorted_indices.first().unwrap();
+            Box::new(BufReader::new(file))
+        }
+        IoType::FromReader(reader) => reader,
+    })
+}
 
-    let last_index = *sorted_indices.last().unwrap();
+/**
+Opens the input, returning an iterator over the records the cut type selects from it
+*/
+pub fn records<'a>(
+    input: IoType<'a>,
+    cut_type: CutType<'a>,
+    options: &CutOptions,
+) -> Result<Records<'a, Box<dyn BufRead + 'a>>, RecutError> {
+    let cutter = Cutter::new(cut_type, options)?;
+
+    cutter.records(open_input(input)?)
+}
 
-    let take_length = last_index + 1;
+/**
+Opens the input, returning an iterator over the raw bytes selected from each line.
+Unlike `records` the input does not need to be UTF-8, only the bytes cut type can be used
+and any other fails with `NotBytesError`
+*/
+pub fn byte_records<'a>(
+    input: IoType<'a>,
+    cut_type: CutType<'a>,
+    options: &CutOptions,
+) -> Result<ByteRecords<'a, Box<dyn BufRead + 'a>>, RecutError> {
+    let cutter = Cutter::new(cut_type, options)?;
+
+    cutter.byte_records(open_input(input)?)
+}
 
-    let byte_map = input_line
-        .bytes()
-        .enumerate()
-        .skip(first_index)
-        .take(take_length)
-        .collect::<HashMap<_, _>>();
+/**
+Resolves the cut against the first line of the input without cutting anything,
+describing the delimiter, list and indices the first line resolves to
+*/
+pub fn explain(
+    input: IoType,
+    cut_type: CutType,
+    options: &CutOptions,
+) -> Result<Explanation, RecutError> {
+    let cutter = Cutter::new(cut_type, options)?;
+
+    cutter.explain(open_input(input)?)
+}
 
-    let mut print_bytes = Vec::with_capacity(length);
+/**
+Cuts the input according to the cut type, sending each selected record to the sink.
+Bytes are cut from the raw input and sent to the sink untouched
+*/
+pub fn cut(
+    input: IoType,
+    cut_type: CutType,
+    options: &CutOptions,
+    sink: &mut dyn RecordSink,
+) -> Result<(), RecutError> {
+    if let CutType::Bytes(_, _) = cut_type {
+        let records = byte_records(input, cut_type, options)?;
+        sink.start(records.cutter().output_delimiter())?;
 
-    for print_index in expanded_indices {
-        print_bytes.push(*byte_map.get(&print_index).unwrap());
+        for record in records {
+            sink.write_bytes(&record?)?;
+        }
+        return sink.finish();
     }
 
-    let print_string = String::from_utf8_lossy(print_bytes.as_slice());
+    let mut records = records(input, cut_type, options)?;
+    sink.start(records.cutter().output_delimiter())?;
 
-    if splits_alowed {
-        println!("{}", print_string);
-    } else {
-        println!("{}", print_string.trim_end_matches("�"))
+    if records.has_header() {
+        if let Some(header) = records.next() {
+            sink.header(&header?)?;
+        }
     }
-}
-
-fn print_by_string_delimiter(
-    input_buffer: Box<dyn BufRead>,
-    delimiter: &str,
-    input_indices: &[UnExpandedIndices],
-) -> Result<(), RecutError> {
-    for line in input_buffer.lines() {
-        let split_line = split_line_quotes(&line?, delimiter);
-
-        print_line_delimited(&split_line, &input_indices);
+    for record in records {
+        sink.write_record(&record?)?;
     }
-    Ok(())
-}
-fn print_by_regex(
-    input_buffer: Box<dyn BufRead>,
-    delimiter: &str,
-    input_indices: &[UnExpandedIndices],
-) -> Result<(), RecutError> {
-    let regex_delim = Regex::new(delimiter)?;
 
-    for line in input_buffer.lines() {
-        let split_line = split_line_regex_quotes(&line?, &regex_delim);
-        print_line_delimited(&split_line, &input_indices);
-    }
-    Ok(())
+    sink.finish()
 }
-fn print_line_delimited(split_line: &[String], input_indices: &[UnExpandedIndices]) {
-    let length = split_line.len();
-    let (sorted_indices, expanded_indices) = expand_indices(input_indices, length);
-    let first_index = *sorted_indices.first().unwrap();
-
-    let last_index = *sorted_indices.last().unwrap();
 
-    let take_length = last_index + 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const CSV: &str = "name,size,qty\n\"b, c\",5,3\nd,7,4\n";
+    const ALIGNED: &str = "NAME    STATUS   AGE\nweb 1   Running  2d\ndb      Pending  5m\n";
+
+    /**
+    Cuts the input with the default options, returning what a `WriteSink` wrote
+    */
+    fn cut_bytes(input: &str, cut_type: CutType) -> Vec<u8> {
+        let mut sink = WriteSink::new(Vec::new());
+        let input = IoType::from_reader(Cursor::new(input));
+        cut(input, cut_type, &CutOptions::default(), &mut sink).unwrap();
+        sink.into_inner()
+    }
 
-    let split_map = split_line
-        .into_iter()
-        .enumerate()
-        .skip(first_index)
-        .take(take_length)
-        .collect::<HashMap<_, _>>();
+    fn cut_string(input: &str, cut_type: CutType) -> String {
+        String::from_utf8(cut_bytes(input, cut_type)).unwrap()
+    }
 
-    let mut print_string = Vec::with_capacity(length);
+    fn cut_missing(
+        input: &str,
+        cut_type: CutType,
+        missing: MissingPolicy,
+    ) -> Result<String, RecutError> {
+        let options = CutOptions {
+            missing,
+            ..CutOptions::default()
+        };
+        let mut sink = WriteSink::new(Vec::new());
+        cut(
+            IoType::from_reader(Cursor::new(input)),
+            cut_type,
+            &options,
+            &mut sink,
+        )?;
+        Ok(String::from_utf8(sink.into_inner()).unwrap())
+    }
 
-    for print_index in expanded_indices {
-        let next = split_map.get(&print_index).unwrap().to_owned().to_owned();
-        print_string.push(next)
+    #[test]
+    fn bytes() {
+        assert_eq!(
+            cut_bytes("héllo\n", CutType::Bytes("0:1", true)),
+            b"h\xc3\n"
+        );
+        assert_eq!(cut_bytes("héllo\n", CutType::Bytes("0:1", false)), b"h\n");
     }
 
-    println!("{}", print_string.join(""));
-}
+    #[test]
+    fn characters() {
+        assert_eq!(cut_string("héllo\n", CutType::Characters("1:2")), "él\n");
+    }
 
-fn print_infer_regex(
-    mut input_buffer: Box<dyn BufRead>,
-    input_indices: &[UnExpandedIndices],
-) -> Result<(), RecutError> {
-    let mut line = String::new();
-    input_buffer.read_line(&mut line)?;
-    let delimiter = infer_delimiter(&line);
-
-    let split_line = split_line_quotes(&line, &delimiter);
-    print_line_delimited(&split_line, &input_indices);
-    for line in input_buffer.lines() {
-        let split_line = split_line_quotes(&line?, &delimiter);
-        print_line_delimited(&split_line, &input_indices);
+    #[test]
+    fn graphemes() {
+        assert_eq!(
+            cut_string("e\u{301}x\n", CutType::Graphemes("0")),
+            "e\u{301}\n"
+        );
     }
-    Ok(())
-}
 
-fn print_match_infer_regex(
-    mut input_buffer: Box<dyn BufRead>,
-    match_str: &str,
-) -> Result<(), RecutError> {
-    let mut line = String::new();
-    input_buffer.read_line(&mut line)?;
-    let delimiter = infer_delimiter(&line);
+    #[test]
+    fn columns() {
+        assert_eq!(cut_string("日本\n", CutType::Columns("2:3")), "本\n");
+    }
 
-    let (split_line, input_indices) = parse_match_indices(match_str, &line, &delimiter)?;
+    #[test]
+    fn fields_infer_delimiter() {
+        let cut_type = CutType::FieldsInferDelimiter("0,2");
+        assert_eq!(cut_string(CSV, cut_type), "name,qty\nb, c,3\nd,4\n");
+    }
 
-    println!("{}", split_line.join(","));
-    for line in input_buffer.lines() {
-        let split_line = split_line_quotes(&line?, &delimiter);
-        print_line_match_delimited(&split_line, &input_indices);
+    #[test]
+    fn fields_regex_delimiter() {
+        let cut_type = CutType::FieldsRegexDelimiter(RangeDelimiter::new("-1", r"\s*;\s*"));
+        assert_eq!(cut_string("a ; b\nc;d\n", cut_type), "b\nd\n");
     }
-    Ok(())
-}
-fn print_line_match_delimited(split_line: &[String], input_indices: &[usize]) {
-    let mut print_string = Vec::with_capacity(input_indices.len());
 
-    let split_indices = split_line
-        .into_iter()
-        .enumerate()
-        .collect::<HashMap<_, _>>();
+    #[test]
+    fn fields_string_delimiter() {
+        let cut_type = CutType::FieldsStringDelimiter(RangeDelimiter::new("1", "::"));
+        assert_eq!(cut_string("a::b\nc::d\n", cut_type), "b\nd\n");
+    }
 
-    for i in input_indices {
-        let next = split_indices.get(&i).unwrap().to_owned().to_owned();
-        print_string.push(next);
+    #[test]
+    fn matches_infer_delimiter() {
+        let cut_type = CutType::MatchesInferDelimiter("qty");
+        assert_eq!(cut_string(CSV, cut_type), "qty\n3\n4\n");
     }
 
-    println!("{}", print_string.join(","));
-}
+    #[test]
+    fn matches_regex_delimiter() {
+        let cut_type = CutType::MatchesRegexDelimiter(RangeDelimiter::new("b", r"\s*;\s*"));
+        assert_eq!(cut_string("a ; b\nc;d\n", cut_type), "b\nd\n");
+    }
 
-fn print_match_string_delimiter(
-    mut input_buffer: Box<dyn BufRead>,
-    delimiter: &str,
-    match_str: &str,
-) -> Result<(), RecutError> {
-    let mut line = String::new();
-    input_buffer.read_line(&mut line)?;
+    #[test]
+    fn matches_string_delimiter() {
+        let cut_type = CutType::MatchesStringDelimiter(RangeDelimiter::new("a", "::"));
+        assert_eq!(cut_string("a::b\nc::d\n", cut_type), "a\nc\n");
+    }
 
-    let (split_line, input_indices) = parse_match_indices(match_str, &line, &delimiter)?;
+    #[test]
+    fn fields_infer_columns() {
+        let cut_type = CutType::FieldsInferColumns("0,1");
+        assert_eq!(
+            cut_string(ALIGNED, cut_type),
+            "NAME STATUS\nweb 1 Running\ndb Pending\n"
+        );
+    }
 
-    println!("{}", split_line.join(","));
-    for line in input_buffer.lines() {
-        let split_line = split_line_quotes(&line?, &delimiter);
-        print_line_match_delimited(&split_line, &input_indices);
+    #[test]
+    fn matches_infer_columns() {
+        let cut_type = CutType::MatchesInferColumns("AGE");
+        assert_eq!(cut_string(ALIGNED, cut_type), "AGE\n2d\n5m\n");
     }
-    Ok(())
-}
 
-fn print_match_regex_delimiter(
-    mut input_buffer: Box<dyn BufRead>,
-    delimiter: &str,
-    match_str: &str,
-) -> Result<(), RecutError> {
-    let regex = Regex::new(delimiter)?;
-    let mut line = String::new();
-    input_buffer.read_line(&mut line)?;
+    #[test]
+    fn fields_fixed_width() {
+        let cut_type = CutType::FieldsFixedWidth(RangeDelimiter::new("1", "3,2,*"));
+        assert_eq!(cut_string("abcdefg\nhijklmn\n", cut_type), "de\nkl\n");
+    }
 
-    let (split_line, input_indices) = parse_match_indices_regex(match_str, &line, &regex)?;
+    #[test]
+    fn matches_fixed_width() {
+        let cut_type = CutType::MatchesFixedWidth(RangeDelimiter::new("AGE", "8,9,*"));
+        assert_eq!(cut_string(ALIGNED, cut_type), "AGE\n2d\n5m\n");
+    }
 
-    println!("{}", split_line.join(","));
-    for line in input_buffer.lines() {
-        let split_line = split_line_regex_quotes(&line?, &regex);
-        print_line_match_delimited(&split_line, &input_indices);
+    #[test]
+    fn empty_input_with_a_given_delimiter() {
+        let cut_type = CutType::FieldsRegexDelimiter(RangeDelimiter::new("0", ","));
+        assert_eq!(cut_string("", cut_type), "");
     }
-    Ok(())
-}
 
-fn infer_delimiter(input_line: &str) -> String {
-    let parse_result = InputParser::parse(Rule::input, input_line).unwrap(); //harcoded should succeed
-
-    let mut potential_delimiters = BTreeMap::new();
-    for parse_pair in parse_result {
-        for iner in parse_pair.into_inner() {
-            match iner.as_rule() {
-                Rule::data => {}
-                Rule::potential_delim => {
-                    let next_delim = potential_delimiters.entry(iner.as_str()).or_insert(0);
-                    *next_delim += 1;
-                }
-                _ => unreachable!(),
-            };
+    #[test]
+    fn truncated_last_line() {
+        let input = "a,b,c\nd,e,f\ng\n";
+        let cut_type = || CutType::FieldsStringDelimiter(RangeDelimiter::new("0,2", ","));
+        match cut_missing(input, cut_type(), MissingPolicy::Error) {
+            Err(RecutError::MissingFieldError(e)) => assert_eq!(e.line, Some(3)),
+            result => panic!("expected a missing field error, got {:?}", result),
         }
+        let skipped = cut_missing(input, cut_type(), MissingPolicy::SkipLine);
+        assert_eq!(skipped.unwrap(), "a,c\nd,f\n");
+        let empty = cut_missing(input, cut_type(), MissingPolicy::Empty);
+        assert_eq!(empty.unwrap(), "a,c\nd,f\ng,\n");
+        let filled = cut_missing(input, cut_type(), MissingPolicy::Fill(String::from("?")));
+        assert_eq!(filled.unwrap(), "a,c\nd,f\ng,?\n");
     }
 
-    potential_delimiters
-        .iter()
-        .next_back()
-        .unwrap()
-        .0
-        .to_owned()
-        .to_owned()
-}
+    #[test]
+    fn empty_input_to_infer_from() {
+        let result = cut_missing(
+            "",
+            CutType::MatchesInferDelimiter("a"),
+            MissingPolicy::Error,
+        );
+        assert!(matches!(result, Err(RecutError::NoInputError)));
+    }
 
-fn expand_indices(input_indices: &[UnExpandedIndices], length: usize) -> (Vec<usize>, Vec<usize>) {
-    // like moduluo  but number wraped  around index for negative numbers
-    let tn = |num: i32| {
-        if num >= 0 {
-            num as usize
-        } else {
-            length - num as usize
-        }
-    };
-    let expanded_indices: Vec<_> = input_indices
-        .into_iter()
-        .flat_map(|range| match range {
-            UnExpandedIndices::Index(num) => vec![*num as usize],
-            UnExpandedIndices::Range(BeginRange::FromStart, EndRange::ToEnd) => {
-                (0..=length).collect()
-            }
-            UnExpandedIndices::Range(BeginRange::FromStart, EndRange::Index(num)) => {
-                (0..=tn(*num)).collect()
-            }
-            UnExpandedIndices::Range(BeginRange::Index(num), EndRange::ToEnd) => {
-                (tn(*num)..=length).collect()
-            }
-            UnExpandedIndices::Range(BeginRange::Index(begin_num), EndRange::Index(end_num)) => {
-                (tn(*begin_num)..=tn(*end_num)).collect()
-            }
-        })
-        .collect();
-
-    let mut sorted_indices = expanded_indices.clone();
-    sorted_indices.sort();
-
-    (sorted_indices, expanded_indices)
+    #[test]
+    fn no_delimiter_to_infer() {
+        let result = cut_missing(
+            "abc\n",
+            CutType::FieldsInferDelimiter("0"),
+            MissingPolicy::Error,
+        );
+        assert!(matches!(
+            result,
+            Err(RecutError::DelimiterInferenceError(_))
+        ));
+    }
+
+    #[test]
+    fn byte_records_only_for_bytes() {
+        let input = IoType::from_reader(Cursor::new("a,b\n"));
+        let result = byte_records(input, CutType::Characters("0"), &CutOptions::default());
+        assert!(matches!(result, Err(RecutError::NotBytesError)));
+    }
 }
@@ -1,41 +1,20 @@
-use crate::field::{split_line_quotes, split_line_regex_quotes};
-use regex::{Regex, RegexSetBuilder};
+use crate::field::{split_line_quotes, Quoting};
+use crate::TrimPolicy;
+use regex::RegexSetBuilder;
+use std::borrow::Cow;
 
 use crate::error;
+use crate::explain::HeaderMatches;
 use error::RecutError;
 
-enum DelimiterType<'a> {
-    String(&'a str),
-    Regex(&'a Regex),
-}
-
-pub fn parse_match_indices(
-    match_str: &str,
-    input_line: &str,
-    delimiter: &str,
-) -> Result<(Vec<String>, Vec<usize>), RecutError> {
-    parse_match(match_str, input_line, &DelimiterType::String(delimiter))
-}
-
-pub fn parse_match_indices_regex(
+/**
+The header fields matched by any pattern in the match string along with their indices
+*/
+pub fn match_header(
     match_str: &str,
-    input_line: &str,
-    delimiter: &Regex,
+    header: &[Cow<str>],
 ) -> Result<(Vec<String>, Vec<usize>), RecutError> {
-    parse_match(match_str, input_line, &DelimiterType::Regex(delimiter))
-}
-
-fn parse_match(
-    match_str: &str,
-    input_line: &str,
-    delim: &DelimiterType,
-) -> Result<(Vec<String>, Vec<usize>), RecutError> {
-    let match_split = split_line_quotes(match_str, ",");
-
-    let line_split = match delim {
-        DelimiterType::String(delimiter) => split_line_quotes(input_line, delimiter),
-        DelimiterType::Regex(delimiter) => split_line_regex_quotes(input_line, delimiter),
-    };
+    let match_split = split_line_quotes(match_str, ",", &Quoting::default(), &TrimPolicy::Both);
 
     let set = RegexSetBuilder::new(match_split.iter())
         .case_insensitive(true)
@@ -44,12 +23,36 @@ fn parse_match(
     let mut indices = Vec::with_capacity(match_split.len());
     let mut first_line_split = Vec::with_capacity(match_split.len());
 
-    for (i, line) in line_split.into_iter().enumerate() {
-        if set.is_match(&line) {
+    for (i, line) in header.iter().enumerate() {
+        if set.is_match(line) {
             indices.push(i);
-            first_line_split.push(line);
+            first_line_split.push(line.to_string());
         }
     }
 
     Ok((first_line_split, indices))
 }
+
+/**
+For each pattern in the match string, the indices and names of the header fields it matches
+*/
+pub fn explain_matches(match_str: &str, header: &[Cow<str>]) -> Result<HeaderMatches, RecutError> {
+    let match_split = split_line_quotes(match_str, ",", &Quoting::default(), &TrimPolicy::Both);
+
+    let set = RegexSetBuilder::new(match_split.iter())
+        .case_insensitive(true)
+        .build()?;
+
+    let mut matched = match_split
+        .iter()
+        .map(|pattern| (pattern.to_string(), Vec::new()))
+        .collect::<Vec<_>>();
+
+    for (i, field) in header.iter().enumerate() {
+        for pattern in set.matches(field).iter() {
+            matched[pattern].1.push((i, field.to_string()));
+        }
+    }
+
+    Ok(matched)
+}
@@ -1,16 +1,21 @@
 use crate::error;
-use error::{RangeError, RecutError};
+use crate::OutOfRange;
+use error::{LineRangeError, RangeError, RecutError};
 use pest::Parser;
+use std::ops::RangeInclusive;
+#[derive(Debug)]
 pub enum BeginRange {
     Index(i32),
     FromStart,
 }
 
+#[derive(Debug)]
 pub enum EndRange {
     Index(i32),
     ToEnd,
 }
 
+#[derive(Debug)]
 pub enum UnExpandedIndices {
     Index(i32),
     Range(BeginRange, EndRange),
@@ -24,7 +29,6 @@ pub struct ListParser;
 Attempts to pass the list argument if sucessfull it then converts the indices to numbers
 and creates and generates a vecor of type  UnExpandedIndices
 */
-
 /* TODO add range errors */
 pub fn parse_indices(input: &str) -> std::result::Result<Vec<UnExpandedIndices>, RecutError> {
     /* Map to act on sucessfull case transfroms parse into  */
@@ -55,8 +59,14 @@ pub fn parse_indices(input: &str) -> std::result::Result<Vec<UnExpandedIndices>,
         .collect::<Result<Vec<UnExpandedIndices>, RecutError>>()
 }
 
+/**
+Ranges with both ends on the same side of the line can be checked straight away,
+ranges mixing a positive and negative end are only checked once the line length is known
+*/
 fn check_range(first_num: i32, second_num: i32) -> Result<UnExpandedIndices, RecutError> {
-    if first_num < second_num {
+    let mixed_signs = (first_num < 0) != (second_num < 0);
+
+    if mixed_signs || first_num < second_num {
         Ok(UnExpandedIndices::Range(
             BeginRange::Index(first_num),
             EndRange::Index(second_num),
@@ -65,3 +75,180 @@ fn check_range(first_num: i32, second_num: i32) -> Result<UnExpandedIndices, Rec
         Err(RecutError::RangeValueError(RangeError {}))
     }
 }
+
+/**
+Converts a list entry into the positions it covers in a line with `length` pieces as an inclusive
+range, so long ranges are never built up in memory. Negative indices count back from the end so -1
+is the last piece, open ends of ranges stop at the first and last piece. Positions outside the line
+are only produced by single indices, or by ranges when the out of range policy is `Missing`,
+they are for the missing policy to handle. A range mixing signs can resolve backwards on a short line
+*/
+pub fn expand_index(
+    entry: &UnExpandedIndices,
+    length: usize,
+    out_of_range: &OutOfRange,
+) -> Result<RangeInclusive<i64>, LineRangeError> {
+    let last = length as i64 - 1;
+    // like moduluo  but number wraped  around index for negative numbers
+    let tn = |num: i32| {
+        if num >= 0 {
+            num as i64
+        } else {
+            length as i64 + num as i64
+        }
+    };
+    let bounded = |begin: i64, end: i64| match out_of_range {
+        OutOfRange::Clamp => begin.max(0)..=end.min(last),
+        OutOfRange::Missing => begin..=end,
+    };
+
+    Ok(match entry {
+        UnExpandedIndices::Index(num) => tn(*num)..=tn(*num),
+        UnExpandedIndices::Range(BeginRange::FromStart, EndRange::ToEnd) => 0..=last,
+        UnExpandedIndices::Range(BeginRange::FromStart, EndRange::Index(num)) => match out_of_range
+        {
+            OutOfRange::Missing if tn(*num) < 0 => tn(*num)..=tn(*num),
+            _ => bounded(0, tn(*num)),
+        },
+        UnExpandedIndices::Range(BeginRange::Index(num), EndRange::ToEnd) => match out_of_range {
+            OutOfRange::Missing if tn(*num) > last => tn(*num)..=tn(*num),
+            _ => bounded(tn(*num), last),
+        },
+        UnExpandedIndices::Range(BeginRange::Index(begin_num), EndRange::Index(end_num)) => {
+            if tn(*begin_num) > tn(*end_num) {
+                return Err(LineRangeError::new(*begin_num, *end_num, length));
+            }
+            bounded(tn(*begin_num), tn(*end_num))
+        }
+    })
+}
+
+/**
+Converts every entry of the list as `expand_index` does
+*/
+pub fn expand_indices(
+    input_indices: &[UnExpandedIndices],
+    length: usize,
+    out_of_range: &OutOfRange,
+) -> Result<Vec<RangeInclusive<i64>>, LineRangeError> {
+    input_indices
+        .iter()
+        .map(|entry| expand_index(entry, length, out_of_range))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BeginRange::FromStart;
+    use EndRange::ToEnd;
+
+    fn range(begin: BeginRange, end: EndRange) -> UnExpandedIndices {
+        UnExpandedIndices::Range(begin, end)
+    }
+
+    /**
+    The positions a single list entry covers in a line of five pieces
+    */
+    fn expand(entry: UnExpandedIndices, out_of_range: OutOfRange) -> Vec<i64> {
+        expand_indices(&[entry], 5, &out_of_range)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn index() {
+        for out_of_range in [OutOfRange::Clamp, OutOfRange::Missing] {
+            let expand = |num| expand(UnExpandedIndices::Index(num), out_of_range.clone());
+            assert_eq!(expand(2), [2]);
+            assert_eq!(expand(-1), [4]);
+            assert_eq!(expand(7), [7]);
+            assert_eq!(expand(-7), [-2]);
+        }
+    }
+
+    #[test]
+    fn from_start_to_end() {
+        for out_of_range in [OutOfRange::Clamp, OutOfRange::Missing] {
+            assert_eq!(
+                expand(range(FromStart, ToEnd), out_of_range.clone()),
+                [0, 1, 2, 3, 4]
+            );
+            let empty_line = expand_indices(&[range(FromStart, ToEnd)], 0, &out_of_range).unwrap();
+            assert_eq!(empty_line[0].clone().count(), 0);
+        }
+    }
+
+    #[test]
+    fn from_start_to_index() {
+        let to = |num| range(FromStart, EndRange::Index(num));
+        assert_eq!(expand(to(2), OutOfRange::Clamp), [0, 1, 2]);
+        assert_eq!(expand(to(2), OutOfRange::Missing), [0, 1, 2]);
+        assert_eq!(expand(to(-4), OutOfRange::Clamp), [0, 1]);
+        assert_eq!(expand(to(-4), OutOfRange::Missing), [0, 1]);
+        assert_eq!(expand(to(7), OutOfRange::Clamp), [0, 1, 2, 3, 4]);
+        assert_eq!(expand(to(7), OutOfRange::Missing), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(expand(to(-7), OutOfRange::Clamp), []);
+        assert_eq!(expand(to(-7), OutOfRange::Missing), [-2]);
+    }
+
+    #[test]
+    fn index_to_end() {
+        let from = |num| range(BeginRange::Index(num), ToEnd);
+        assert_eq!(expand(from(3), OutOfRange::Clamp), [3, 4]);
+        assert_eq!(expand(from(3), OutOfRange::Missing), [3, 4]);
+        assert_eq!(expand(from(-2), OutOfRange::Clamp), [3, 4]);
+        assert_eq!(expand(from(-2), OutOfRange::Missing), [3, 4]);
+        assert_eq!(expand(from(7), OutOfRange::Clamp), []);
+        assert_eq!(expand(from(7), OutOfRange::Missing), [7]);
+        assert_eq!(expand(from(-7), OutOfRange::Clamp), [0, 1, 2, 3, 4]);
+        assert_eq!(
+            expand(from(-7), OutOfRange::Missing),
+            [-2, -1, 0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn positive_index_to_index() {
+        let between = |begin, end| range(BeginRange::Index(begin), EndRange::Index(end));
+        assert_eq!(expand(between(1, 3), OutOfRange::Clamp), [1, 2, 3]);
+        assert_eq!(expand(between(1, 3), OutOfRange::Missing), [1, 2, 3]);
+        assert_eq!(expand(between(3, 7), OutOfRange::Clamp), [3, 4]);
+        assert_eq!(expand(between(3, 7), OutOfRange::Missing), [3, 4, 5, 6, 7]);
+        assert_eq!(expand(between(6, 8), OutOfRange::Clamp), []);
+        assert_eq!(expand(between(6, 8), OutOfRange::Missing), [6, 7, 8]);
+    }
+
+    #[test]
+    fn negative_index_to_index() {
+        let between = |begin, end| range(BeginRange::Index(begin), EndRange::Index(end));
+        assert_eq!(expand(between(-3, -1), OutOfRange::Clamp), [2, 3, 4]);
+        assert_eq!(expand(between(-3, -1), OutOfRange::Missing), [2, 3, 4]);
+        assert_eq!(expand(between(-7, -4), OutOfRange::Clamp), [0, 1]);
+        assert_eq!(expand(between(-7, -4), OutOfRange::Missing), [-2, -1, 0, 1]);
+    }
+
+    #[test]
+    fn mixed_index_to_index() {
+        let between = |begin, end| range(BeginRange::Index(begin), EndRange::Index(end));
+        assert_eq!(expand(between(1, -2), OutOfRange::Clamp), [1, 2, 3]);
+        assert_eq!(expand(between(1, -2), OutOfRange::Missing), [1, 2, 3]);
+        assert_eq!(expand(between(-2, 7), OutOfRange::Clamp), [3, 4]);
+        assert_eq!(expand(between(-2, 7), OutOfRange::Missing), [3, 4, 5, 6, 7]);
+        assert_eq!(expand(between(-7, 1), OutOfRange::Clamp), [0, 1]);
+        assert_eq!(expand(between(-7, 1), OutOfRange::Missing), [-2, -1, 0, 1]);
+        for out_of_range in [OutOfRange::Clamp, OutOfRange::Missing] {
+            // resolves to 3:1 on a line of five
+            assert!(expand_indices(&[between(3, -4)], 5, &out_of_range).is_err());
+        }
+    }
+
+    #[test]
+    fn long_ranges_are_not_built() {
+        let long = range(BeginRange::Index(0), EndRange::Index(300_000_000));
+        let expanded = expand_indices(&[long], 2, &OutOfRange::Missing).unwrap();
+        assert_eq!(expanded, [0..=300_000_000]);
+    }
+}
@@ -0,0 +1,478 @@
+use crate::error::RecutError;
+use std::{borrow::Cow, io::Write};
+use unicode_width::UnicodeWidthStr;
+
+/**
+Destination for the records recut selects, each record arrives as the fields
+that were selected from a single line of input
+*/
+pub trait RecordSink {
+    /**
+    Called once before any records are written with the delimiter to place between fields
+    */
+    fn start(&mut self, _output_delimiter: &str) -> Result<(), RecutError> {
+        Ok(())
+    }
+
+    /**
+    Called before any records with the matched header fields when the cut type matches a header,
+    by default the header is written as the first record
+    */
+    fn header(&mut self, header: &[Cow<str>]) -> Result<(), RecutError> {
+        self.write_record(header)
+    }
+
+    fn write_record(&mut self, record: &[Cow<str>]) -> Result<(), RecutError>;
+
+    /**
+    Receives records cut from raw bytes, by default invalid UTF-8 is replaced and the record written as text
+    */
+    fn write_bytes(&mut self, record: &[Cow<[u8]>]) -> Result<(), RecutError> {
+        let record = record
+            .iter()
+            .map(|bytes| String::from_utf8_lossy(bytes))
+            .collect::<Vec<_>>();
+        self.write_record(&record)
+    }
+
+    /**
+    Called once after the last record has been written
+    */
+    fn finish(&mut self) -> Result<(), RecutError> {
+        Ok(())
+    }
+}
+
+/**
+Writes every record to a writer as a single line, with fields joined by the output delimiter
+*/
+pub struct WriteSink<W: Write> {
+    writer: W,
+    separator: String,
+}
+
+impl<W: Write> WriteSink<W> {
+    pub fn new(writer: W) -> WriteSink<W> {
+        WriteSink {
+            writer,
+            separator: String::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordSink for WriteSink<W> {
+    fn start(&mut self, output_delimiter: &str) -> Result<(), RecutError> {
+        self.separator = output_delimiter.to_owned();
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[Cow<str>]) -> Result<(), RecutError> {
+        writeln!(self.writer, "{}", record.join(&self.separator)).map_err(RecutError::OutputError)
+    }
+
+    fn write_bytes(&mut self, record: &[Cow<[u8]>]) -> Result<(), RecutError> {
+        let mut line = record.join(self.separator.as_bytes());
+        line.push(b'\n');
+        self.writer
+            .write_all(&line)
+            .map_err(RecutError::OutputError)
+    }
+}
+
+/**
+Writes every record as a line of CSV, as in RFC 4180 a field containing the delimiter, a quote
+or a line break is quoted with any quotes inside it doubled
+*/
+pub struct CsvSink<W: Write> {
+    writer: W,
+    separator: String,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> CsvSink<W> {
+        CsvSink {
+            writer,
+            separator: String::from(","),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn quote<'f>(&self, field: &'f str) -> Cow<'f, str> {
+        let needs_quotes = (!self.separator.is_empty() && field.contains(self.separator.as_str()))
+            || field.contains(['"', '\n', '\r']);
+        if needs_quotes {
+            Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+        } else {
+            Cow::Borrowed(field)
+        }
+    }
+}
+
+impl<W: Write> RecordSink for CsvSink<W> {
+    fn start(&mut self, output_delimiter: &str) -> Result<(), RecutError> {
+        self.separator = output_delimiter.to_owned();
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[Cow<str>]) -> Result<(), RecutError> {
+        let record = record
+            .iter()
+            .map(|field| self.quote(field))
+            .collect::<Vec<_>>();
+        writeln!(self.writer, "{}", record.join(&self.separator)).map_err(RecutError::OutputError)
+    }
+}
+
+/**
+Writes records as JSON, either as one array of every record or with `ndjson` one record per line.
+When a header is matched each record is an object keyed by the header names, otherwise an array.
+A name repeated in the header has its index appended, so `a,a` are keyed `a` and `a_1`
+*/
+pub struct JsonSink<W: Write> {
+    writer: W,
+    ndjson: bool,
+    header: Option<Vec<String>>,
+    written: usize,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W) -> JsonSink<W> {
+        JsonSink {
+            writer,
+            ndjson: false,
+            header: None,
+            written: 0,
+        }
+    }
+
+    pub fn ndjson(writer: W) -> JsonSink<W> {
+        JsonSink {
+            ndjson: true,
+            ..JsonSink::new(writer)
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/**
+Quotes a string for JSON, escaping quotes, backslashes and control characters
+*/
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/**
+Header names made unique to be used as keys, a name already taken has the index of its field appended
+*/
+fn unique_keys(header: &[Cow<str>]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(header.len());
+    for (i, name) in header.iter().enumerate() {
+        let mut key = name.to_string();
+        while keys.contains(&key) {
+            key = format!("{}_{}", key, i);
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+impl<W: Write> RecordSink for JsonSink<W> {
+    fn header(&mut self, header: &[Cow<str>]) -> Result<(), RecutError> {
+        self.header = Some(unique_keys(header));
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[Cow<str>]) -> Result<(), RecutError> {
+        let values = record.iter().map(|field| json_string(field));
+        let json = match &self.header {
+            Some(header) => {
+                let members = values
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let key = header
+                            .get(i)
+                            .map_or_else(|| i.to_string(), String::to_owned);
+                        format!("{}:{}", json_string(&key), value)
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", members.join(","))
+            }
+            None => format!("[{}]", values.collect::<Vec<_>>().join(",")),
+        };
+
+        let separator = match (self.ndjson, self.written) {
+            (true, _) => "",
+            (false, 0) => "[\n",
+            (false, _) => ",\n",
+        };
+        self.written += 1;
+        write!(self.writer, "{}{}", separator, json).map_err(RecutError::OutputError)?;
+        if self.ndjson {
+            writeln!(self.writer).map_err(RecutError::OutputError)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), RecutError> {
+        let end = match (self.ndjson, self.written) {
+            (true, _) => "",
+            (false, 0) => "[]\n",
+            (false, _) => "\n]\n",
+        };
+        write!(self.writer, "{}", end).map_err(RecutError::OutputError)
+    }
+}
+
+/**
+Buffers every record then writes them as a table with each column padded to its widest field,
+measured in terminal display columns. With `markdown` a pipe table is written instead, where
+the matched header or otherwise the first record is the header row above a separator row
+*/
+pub struct TableSink<W: Write> {
+    writer: W,
+    markdown: bool,
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> TableSink<W> {
+    pub fn new(writer: W) -> TableSink<W> {
+        TableSink {
+            writer,
+            markdown: false,
+            header: None,
+            rows: vec![],
+        }
+    }
+
+    pub fn markdown(writer: W) -> TableSink<W> {
+        TableSink {
+            markdown: true,
+            ..TableSink::new(writer)
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /**
+    A field as it is shown in a cell, line breaks would break the table up
+    */
+    fn cell(&self, field: &str) -> String {
+        if self.markdown {
+            field
+                .replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace('\n', "<br>")
+        } else {
+            field.replace("\r\n", " ").replace('\n', " ")
+        }
+    }
+
+    fn write_row(&mut self, row: &[String], widths: &[usize]) -> Result<(), RecutError> {
+        let cells = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| {
+                let cell = row.get(i).map_or("", String::as_str);
+                // the last column of a plain table is not padded so lines do not end in spaces
+                if i + 1 == widths.len() && !self.markdown {
+                    cell.to_owned()
+                } else {
+                    format!("{}{}", cell, " ".repeat(width - cell.width()))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if self.markdown {
+            writeln!(self.writer, "| {} |", cells.join(" | "))
+        } else {
+            writeln!(self.writer, "{}", cells.join("  "))
+        }
+        .map_err(RecutError::OutputError)
+    }
+}
+
+impl<W: Write> RecordSink for TableSink<W> {
+    fn header(&mut self, header: &[Cow<str>]) -> Result<(), RecutError> {
+        self.header = Some(header.iter().map(|name| self.cell(name)).collect());
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[Cow<str>]) -> Result<(), RecutError> {
+        let row = record.iter().map(|field| self.cell(field)).collect();
+        self.rows.push(row);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), RecutError> {
+        let mut rows = std::mem::take(&mut self.rows);
+        let header = match self.header.take() {
+            Some(header) => Some(header),
+            None if self.markdown && !rows.is_empty() => Some(rows.remove(0)),
+            None => None,
+        };
+
+        let columns = header.iter().chain(&rows).map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![if self.markdown { 3 } else { 0 }; columns];
+        for row in header.iter().chain(&rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+
+        if let Some(header) = &header {
+            self.write_row(header, &widths)?;
+            if self.markdown {
+                let separator = widths
+                    .iter()
+                    .map(|width| "-".repeat(*width))
+                    .collect::<Vec<_>>();
+                self.write_row(&separator, &widths)?;
+            }
+        }
+        for row in &rows {
+            self.write_row(row, &widths)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'f>(values: &[&'f str]) -> Vec<Cow<'f, str>> {
+        values.iter().map(|&value| Cow::Borrowed(value)).collect()
+    }
+
+    /**
+    Writes a header, when there is one, and the records to a sink the way `cut` would
+    */
+    fn write<S: RecordSink>(
+        mut sink: S,
+        delimiter: &str,
+        header: Option<&[&str]>,
+        records: &[&[&str]],
+    ) -> S {
+        sink.start(delimiter).unwrap();
+        if let Some(header) = header {
+            sink.header(&fields(header)).unwrap();
+        }
+        for record in records {
+            sink.write_record(&fields(record)).unwrap();
+        }
+        sink.finish().unwrap();
+        sink
+    }
+
+    fn csv(delimiter: &str, records: &[&[&str]]) -> String {
+        let sink = write(CsvSink::new(Vec::new()), delimiter, None, records);
+        String::from_utf8(sink.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let record: &[&str] = &["a,b", "say \"hi\"", "x\ny", "c\rd", "plain"];
+        assert_eq!(
+            csv(",", &[record]),
+            "\"a,b\",\"say \"\"hi\"\"\",\"x\ny\",\"c\rd\",plain\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_for_the_output_delimiter() {
+        assert_eq!(csv(";", &[&["a,b", "a;b"]]), "a,b;\"a;b\"\n");
+    }
+
+    fn json(sink: JsonSink<Vec<u8>>, header: Option<&[&str]>, records: &[&[&str]]) -> String {
+        let sink = write(sink, ",", header, records);
+        String::from_utf8(sink.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn json_escapes_values() {
+        let record: &[&str] = &["back\\slash", "\"q\"", "a\tb\nc\r", "\u{1}\u{7f}"];
+        assert_eq!(
+            json(JsonSink::new(Vec::new()), None, &[record]),
+            "[\n[\"back\\\\slash\",\"\\\"q\\\"\",\"a\\tb\\nc\\r\",\"\\u0001\\u007f\"]\n]\n"
+        );
+    }
+
+    #[test]
+    fn json_duplicate_header_keys() {
+        let header: &[&str] = &["a", "a", "b"];
+        assert_eq!(
+            json(JsonSink::new(Vec::new()), Some(header), &[&["1", "2", "3"]]),
+            "[\n{\"a\":\"1\",\"a_1\":\"2\",\"b\":\"3\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn json_empty_input() {
+        assert_eq!(json(JsonSink::new(Vec::new()), None, &[]), "[]\n");
+        assert_eq!(json(JsonSink::ndjson(Vec::new()), None, &[]), "");
+    }
+
+    #[test]
+    fn ndjson_one_record_per_line() {
+        let header: &[&str] = &["k"];
+        assert_eq!(
+            json(
+                JsonSink::ndjson(Vec::new()),
+                Some(header),
+                &[&["x"], &["y"]]
+            ),
+            "{\"k\":\"x\"}\n{\"k\":\"y\"}\n"
+        );
+    }
+
+    fn table(sink: TableSink<Vec<u8>>, header: Option<&[&str]>, records: &[&[&str]]) -> String {
+        let sink = write(sink, ",", header, records);
+        String::from_utf8(sink.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn table_pads_wide_characters() {
+        let records: &[&[&str]] = &[&["日本", "x"], &["abc", "y"], &["a", "z"]];
+        assert_eq!(
+            table(TableSink::new(Vec::new()), None, records),
+            "日本  x\nabc   y\na     z\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let header: &[&str] = &["name", "日本"];
+        let records: &[&[&str]] = &[&["a|b", "c\nd"]];
+        assert_eq!(
+            table(TableSink::markdown(Vec::new()), Some(header), records),
+            "| name | 日本   |\n| ---- | ------ |\n| a\\|b | c<br>d |\n"
+        );
+    }
+}